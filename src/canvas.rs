@@ -15,7 +15,12 @@ use pathfinder_geometry::rect::RectI;
 use pathfinder_geometry::vector::Vector2I;
 use std::cmp;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::error::GlyphLoadingError;
+use crate::gamma::{self, GammaLut};
 use crate::utils;
 
 lazy_static! {
@@ -74,6 +79,35 @@ impl Canvas {
         }
     }
 
+    /// Corrects the coverage in this canvas for gamma and contrast, for text drawn in the given
+    /// sRGB color. See the `gamma` module.
+    ///
+    /// For `Format::A8`, the correction depends on the luminance of the color. For RGB and RGBA
+    /// canvases, as produced by subpixel antialiasing, each color channel is corrected according
+    /// to the same channel of the text color, and the alpha channel according to its luminance.
+    pub fn apply_gamma_correction(&mut self, gamma_lut: &GammaLut, text_color: [u8; 3]) {
+        let luminance = gamma::luminance(text_color);
+        let bytes_per_pixel = self.format.bytes_per_pixel() as usize;
+        let row_length = self.size.x() as usize * bytes_per_pixel;
+        for y in 0..(self.size.y() as usize) {
+            let row_start = y * self.stride;
+            let row = &mut self.pixels[row_start..(row_start + row_length)];
+            match self.format {
+                Format::A8 => gamma_lut.correct_in_place(row, luminance),
+                Format::Rgb24 | Format::Rgba32 => {
+                    for pixel in row.chunks_mut(bytes_per_pixel) {
+                        for (channel, &color) in pixel.iter_mut().zip(text_color.iter()) {
+                            *channel = gamma_lut.correct(*channel, color);
+                        }
+                        if let Some(alpha) = pixel.get_mut(3) {
+                            *alpha = gamma_lut.correct(*alpha, luminance);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub(crate) fn blit_from_canvas(&mut self, src: &Canvas) {
         self.blit_from(
@@ -249,6 +283,7 @@ impl Format {
 
 /// The antialiasing strategy that should be used when rasterizing glyphs.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum RasterizationOptions {
     /// "Black-and-white" rendering. Each pixel is either entirely on or off.
     Bilevel,
@@ -256,6 +291,151 @@ pub enum RasterizationOptions {
     GrayscaleAa,
     /// Subpixel RGB antialiasing, for LCD screens.
     SubpixelAa,
+    /// Grayscale antialiasing performed by font-kit's own rasterizer from the unhinted outline,
+    /// ignoring any hinting options.
+    ///
+    /// The output is the same on every platform and with every loader that produces the same
+    /// outline, which makes it suitable for golden-image tests. For RGB and RGBA canvases, the
+    /// coverage is written to every channel. Glyphs without an outline, such as bitmap-only
+    /// glyphs, fail with `GlyphLoadingError::NoOutline`.
+    Deterministic,
+}
+
+/// How a glyph is rasterized, as reported by `Loader::rasterize_glyph_with_info()` and
+/// `Loader::raster_bounds_with_info()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlyphRasterInfo {
+    /// Which of the font's representations of the glyph is drawn.
+    pub representation: GlyphRepresentation,
+    /// The styles that the loader synthesizes rather than taking from the font.
+    pub synthesis: Synthesis,
+}
+
+/// A representation of a glyph in a font.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlyphRepresentation {
+    /// The outline of the glyph, without hinting.
+    Outline,
+    /// The outline of the glyph, grid-fitted according to the hinting options.
+    HintedOutline,
+    /// An embedded bitmap from the `CBDT`, `EBDT`, or `sbix` table.
+    Bitmap {
+        /// The size of the bitmap strike, in pixels per em.
+        strike_ppem: u16,
+        /// The factor by which the bitmap is scaled to reach the requested size.
+        scale: f32,
+        /// True if the bitmap is in color, in which case it shouldn't be tinted with the text
+        /// color.
+        color: bool,
+    },
+    /// The layers of a color glyph from the `COLR` table, composited by the loader's rasterizer.
+    /// See `Loader::color_layers()`.
+    ///
+    /// Loaders whose rasterizers don't composite color layers draw the outline of the base glyph
+    /// instead; use `Loader::rasterize_color_layers()` to draw the layers with those.
+    ColorLayers,
+}
+
+/// Styles that a loader applies to glyphs itself because the font doesn't provide them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Synthesis {
+    /// The glyphs are emboldened.
+    pub bold: bool,
+    /// The glyphs are slanted.
+    pub oblique: bool,
+}
+
+/// Limits on the work done by a call that rasterizes glyphs.
+///
+/// Fonts with pathological outlines can take a very long time to rasterize. A budget bounds that
+/// time, either with a deadline or with a token that another thread can cancel. The default
+/// budget is unlimited.
+///
+/// The budget is checked as rasterization proceeds with `RasterizationOptions::Deterministic`.
+/// Platform rasterizers can't be interrupted, so with other options the budget is only checked
+/// before each glyph.
+#[derive(Clone, Debug, Default)]
+pub struct RasterizationBudget {
+    deadline: Option<Instant>,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl RasterizationBudget {
+    /// Returns an unlimited budget.
+    #[inline]
+    pub fn new() -> RasterizationBudget {
+        RasterizationBudget::default()
+    }
+
+    /// Stops rasterization once the given amount of time has elapsed, starting now.
+    #[inline]
+    pub fn with_timeout(self, timeout: Duration) -> RasterizationBudget {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Stops rasterization once the given instant has passed.
+    #[inline]
+    pub fn with_deadline(mut self, deadline: Instant) -> RasterizationBudget {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops rasterization once the given token has been cancelled.
+    #[inline]
+    pub fn with_cancellation_token(
+        mut self,
+        cancellation_token: CancellationToken,
+    ) -> RasterizationBudget {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Returns the deadline of this budget, if any.
+    #[inline]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns an error if the token of this budget has been cancelled or its deadline has passed.
+    pub fn check(&self) -> Result<(), GlyphLoadingError> {
+        if let Some(ref cancellation_token) = self.cancellation_token {
+            if cancellation_token.is_cancelled() {
+                return Err(GlyphLoadingError::Cancelled);
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(GlyphLoadingError::BudgetExceeded);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A flag that cancels rasterization with a `RasterizationBudget` when set.
+///
+/// Clones share the same flag, so a token can be cancelled from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new token that hasn't been cancelled.
+    #[inline]
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels any rasterization using this token.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    /// Returns true if and only if this token has been cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
 trait Blit {
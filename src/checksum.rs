@@ -0,0 +1,188 @@
+// font-kit/src/checksum.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Integrity checks of OpenType font data.
+//!
+//! Every table in the table directory records a checksum of its contents, and the `head` table
+//! records a checksum adjustment that makes the checksum of the whole font a fixed value. Tools
+//! that rewrite fonts must update both, so a mismatch indicates corrupted or carelessly edited
+//! data.
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/otff#calculating-checksums
+
+use crate::tables;
+
+const COLLECTION_TAG: u32 = 0x74746366; // 'ttcf'
+const CHECKSUM_MAGIC: u32 = 0xb1b0afba;
+const TABLE_RECORD_SIZE: usize = 16;
+const CHECKSUM_ADJUSTMENT_OFFSET: usize = 8;
+
+/// The result of verifying the checksums of a font.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChecksumReport {
+    /// The checksum of each table, in table directory order.
+    pub tables: Vec<TableChecksum>,
+    /// The checksum adjustment of the whole font.
+    ///
+    /// This is `None` if the font has no `head` table or is a member of a collection. The layout
+    /// of a collection invalidates the checksum adjustments of its members, so the OpenType
+    /// specification requires that they be ignored.
+    pub checksum_adjustment: Option<ChecksumAdjustment>,
+}
+
+/// The checksum of a single table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableChecksum {
+    /// The tag of the table, such as `glyf`.
+    pub tag: u32,
+    /// The checksum recorded in the table directory.
+    pub expected: u32,
+    /// The checksum of the table data, or `None` if the table lies outside the font data.
+    pub actual: Option<u32>,
+}
+
+/// The `checksumAdjustment` field of the `head` table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChecksumAdjustment {
+    /// The value recorded in the `head` table.
+    pub expected: u32,
+    /// The value computed from the font data.
+    pub actual: u32,
+}
+
+impl ChecksumReport {
+    /// Returns true if and only if every table checksum and the checksum adjustment match.
+    pub fn is_valid(&self) -> bool {
+        self.tables.iter().all(TableChecksum::is_valid)
+            && self
+                .checksum_adjustment
+                .iter()
+                .all(ChecksumAdjustment::is_valid)
+    }
+}
+
+impl TableChecksum {
+    /// Returns true if and only if the recorded checksum matches the table data.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.actual == Some(self.expected)
+    }
+}
+
+impl ChecksumAdjustment {
+    /// Returns true if and only if the recorded checksum adjustment matches the font data.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.actual == self.expected
+    }
+}
+
+/// Verifies the checksums of the font in `font_data`.
+///
+/// If the data is a collection, the member at `font_index` is verified. Returns `None` if the
+/// table directory can't be read or the collection has no such member.
+pub(crate) fn verify_checksums(font_data: &[u8], font_index: u32) -> Option<ChecksumReport> {
+    if tables::read_u32(font_data, 0)? != COLLECTION_TAG {
+        return verify_font_checksums(font_data, 0, true);
+    }
+
+    if font_index >= tables::read_u32(font_data, 8)? {
+        return None;
+    }
+    let offset = tables::read_u32(font_data, 12 + font_index as usize * 4)? as usize;
+    verify_font_checksums(font_data, offset, false)
+}
+
+fn verify_font_checksums(
+    font_data: &[u8],
+    offset: usize,
+    verify_checksum_adjustment: bool,
+) -> Option<ChecksumReport> {
+    let table_count = tables::read_u16(font_data, offset + 4)? as usize;
+    let directory_size = 12 + table_count * TABLE_RECORD_SIZE;
+    let directory = font_data.get(offset..(offset + directory_size))?;
+
+    let mut font_checksum = checksum(directory);
+    let mut table_checksums = Vec::with_capacity(table_count);
+    let mut stored_checksum_adjustment = None;
+    for table_index in 0..table_count {
+        let record_offset = 12 + table_index * TABLE_RECORD_SIZE;
+        let tag = tables::read_u32(directory, record_offset)?;
+        let expected = tables::read_u32(directory, record_offset + 4)?;
+        let table = table_data(font_data, directory, record_offset);
+
+        let actual = match table {
+            None => None,
+            Some(table) if tag == tables::TABLE_TAG_HEAD => {
+                // The checksum of `head` is computed as though `checksumAdjustment` were zero.
+                stored_checksum_adjustment = tables::read_u32(table, CHECKSUM_ADJUSTMENT_OFFSET);
+                Some(checksum(table).wrapping_sub(stored_checksum_adjustment.unwrap_or(0)))
+            }
+            Some(table) => Some(checksum(table)),
+        };
+        font_checksum = font_checksum.wrapping_add(actual.unwrap_or(0));
+        table_checksums.push(TableChecksum {
+            tag,
+            expected,
+            actual,
+        });
+    }
+
+    Some(ChecksumReport {
+        tables: table_checksums,
+        checksum_adjustment: stored_checksum_adjustment
+            .filter(|_| verify_checksum_adjustment)
+            .map(|expected| ChecksumAdjustment {
+                expected,
+                actual: CHECKSUM_MAGIC.wrapping_sub(font_checksum),
+            }),
+    })
+}
+
+fn table_data<'a>(font_data: &'a [u8], directory: &[u8], record_offset: usize) -> Option<&'a [u8]> {
+    let table_offset = tables::read_u32(directory, record_offset + 8)? as usize;
+    let table_length = tables::read_u32(directory, record_offset + 12)? as usize;
+    font_data.get(table_offset..table_offset.checked_add(table_length)?)
+}
+
+// Sums the data as big-endian 32-bit integers, padding the end with zeroes.
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{checksum, verify_checksums};
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum(&[]), 0);
+        assert_eq!(
+            checksum(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02]),
+            3
+        );
+        assert_eq!(checksum(&[0x01, 0x02]), 0x01020000);
+        assert_eq!(
+            checksum(&[0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x02]),
+            1
+        );
+    }
+
+    #[test]
+    fn test_verify_checksums_of_missing_collection_member() {
+        // A `ttcf` header for a collection with no fonts.
+        let font_data = [b't', b't', b'c', b'f', 0, 1, 0, 0, 0, 0, 0, 0];
+        assert!(verify_checksums(&font_data, 0).is_none());
+    }
+}
@@ -0,0 +1,383 @@
+// font-kit/src/classification.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Typeface classifications from the OpenType `OS/2` table: the IBM font family class and the
+//! PANOSE numbers.
+//!
+//! These describe the appearance of a face independently of its name, so they are useful for
+//! picking a similar face when the requested one isn't installed. See the [OpenType
+//! specification] for details.
+//!
+//! [OpenType specification]: https://docs.microsoft.com/en-us/typography/opentype/spec/os2
+
+/// The classification of a font face, as stored in its `OS/2` table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Classification {
+    /// The IBM font family class.
+    pub family_class: FamilyClass,
+    /// The IBM font family subclass. Its meaning depends on `family_class`.
+    pub family_subclass: u8,
+    /// The PANOSE classification.
+    pub panose: Panose,
+}
+
+impl Classification {
+    /// Returns true if the face has serifs, false if it's sans-serif, or `None` if neither the
+    /// family class nor the PANOSE numbers say.
+    pub fn is_serif(&self) -> Option<bool> {
+        match self.family_class {
+            FamilyClass::NoClassification | FamilyClass::Reserved(_) => {
+                self.panose.serif_style().and_then(SerifStyle::is_serif)
+            }
+            FamilyClass::SansSerif => Some(false),
+            family_class => {
+                if family_class.is_serif() {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// The IBM font family class of a face.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FamilyClass {
+    /// The face has no classification.
+    NoClassification,
+    /// Serifs with a diagonal stress and bracketed serifs, e.g. Garamond.
+    OldstyleSerifs,
+    /// Serifs with a nearly vertical stress, e.g. Times New Roman.
+    TransitionalSerifs,
+    /// Serifs with a vertical stress and high stroke contrast, e.g. Bodoni.
+    ModernSerifs,
+    /// Heavy, bracketed serifs, e.g. Clarendon.
+    ClarendonSerifs,
+    /// Square, unbracketed serifs, e.g. Rockwell.
+    SlabSerifs,
+    /// Serifs that don't fit the other serif classes, e.g. Souvenir.
+    FreeformSerifs,
+    /// Sans-serif faces, e.g. Helvetica.
+    SansSerif,
+    /// Highly decorated or stylized faces.
+    Ornamentals,
+    /// Faces that simulate handwriting or calligraphy.
+    Scripts,
+    /// Faces made up of symbols rather than letters.
+    Symbolic,
+    /// A class ID that the OpenType specification reserves.
+    Reserved(u8),
+}
+
+impl FamilyClass {
+    /// Converts a class ID, the high byte of the `sFamilyClass` field, to a family class.
+    pub fn from_id(class_id: u8) -> FamilyClass {
+        match class_id {
+            0 => FamilyClass::NoClassification,
+            1 => FamilyClass::OldstyleSerifs,
+            2 => FamilyClass::TransitionalSerifs,
+            3 => FamilyClass::ModernSerifs,
+            4 => FamilyClass::ClarendonSerifs,
+            5 => FamilyClass::SlabSerifs,
+            7 => FamilyClass::FreeformSerifs,
+            8 => FamilyClass::SansSerif,
+            9 => FamilyClass::Ornamentals,
+            10 => FamilyClass::Scripts,
+            12 => FamilyClass::Symbolic,
+            class_id => FamilyClass::Reserved(class_id),
+        }
+    }
+
+    /// Returns true if this is one of the serif classes.
+    pub fn is_serif(self) -> bool {
+        matches!(
+            self,
+            FamilyClass::OldstyleSerifs
+                | FamilyClass::TransitionalSerifs
+                | FamilyClass::ModernSerifs
+                | FamilyClass::ClarendonSerifs
+                | FamilyClass::SlabSerifs
+                | FamilyClass::FreeformSerifs
+        )
+    }
+}
+
+/// The ten PANOSE classification numbers of a face.
+///
+/// The first number is the family kind, which determines the meaning of the other nine. A zero
+/// means "any" and a one means "no fit".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Panose(pub [u8; 10]);
+
+impl Panose {
+    /// Returns the family kind, which determines the meaning of the remaining numbers.
+    #[inline]
+    pub fn family_kind(&self) -> PanoseFamilyKind {
+        match self.0[0] {
+            0 => PanoseFamilyKind::Any,
+            1 => PanoseFamilyKind::NoFit,
+            2 => PanoseFamilyKind::LatinText,
+            3 => PanoseFamilyKind::LatinHandWritten,
+            4 => PanoseFamilyKind::LatinDecorative,
+            5 => PanoseFamilyKind::LatinSymbol,
+            family_kind => PanoseFamilyKind::Other(family_kind),
+        }
+    }
+
+    /// Returns the serif style of a Latin text face, or `None` for other family kinds.
+    pub fn serif_style(&self) -> Option<SerifStyle> {
+        if self.family_kind() != PanoseFamilyKind::LatinText {
+            return None;
+        }
+        Some(match self.0[1] {
+            0 => SerifStyle::Any,
+            1 => SerifStyle::NoFit,
+            2 => SerifStyle::Cove,
+            3 => SerifStyle::ObtuseCove,
+            4 => SerifStyle::SquareCove,
+            5 => SerifStyle::ObtuseSquareCove,
+            6 => SerifStyle::Square,
+            7 => SerifStyle::Thin,
+            8 => SerifStyle::Oval,
+            9 => SerifStyle::Exaggerated,
+            10 => SerifStyle::Triangle,
+            11 => SerifStyle::NormalSans,
+            12 => SerifStyle::ObtuseSans,
+            13 => SerifStyle::PerpendicularSans,
+            14 => SerifStyle::Flared,
+            15 => SerifStyle::Rounded,
+            serif_style => SerifStyle::Other(serif_style),
+        })
+    }
+
+    /// Returns true if this is a Latin text face whose proportion is monospaced.
+    #[inline]
+    pub fn is_monospace(&self) -> bool {
+        self.family_kind() == PanoseFamilyKind::LatinText && self.0[3] == 9
+    }
+
+    /// Returns a measure of how different this face looks from `other`: zero if they are
+    /// identical, and larger the more they differ.
+    ///
+    /// Numbers that either face leaves as "any" or "no fit" are ignored. Returns `None` if the
+    /// faces are of different family kinds, or either doesn't specify one, since the numbers then
+    /// aren't comparable.
+    pub fn distance(&self, other: &Panose) -> Option<u32> {
+        match self.family_kind() {
+            PanoseFamilyKind::Any | PanoseFamilyKind::NoFit => return None,
+            family_kind if family_kind != other.family_kind() => return None,
+            _ => {}
+        }
+        Some(
+            self.0[1..]
+                .iter()
+                .zip(other.0[1..].iter())
+                .filter(|&(&a, &b)| a > 1 && b > 1)
+                .map(|(&a, &b)| {
+                    let difference = (a as i32 - b as i32).unsigned_abs();
+                    difference * difference
+                })
+                .sum(),
+        )
+    }
+}
+
+/// The PANOSE family kind of a face.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PanoseFamilyKind {
+    /// Any family kind.
+    Any,
+    /// No family kind fits.
+    NoFit,
+    /// Latin text faces.
+    LatinText,
+    /// Latin handwritten faces.
+    LatinHandWritten,
+    /// Latin decorative faces.
+    LatinDecorative,
+    /// Latin symbol faces.
+    LatinSymbol,
+    /// A family kind not listed here.
+    Other(u8),
+}
+
+/// The PANOSE serif style of a Latin text face.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SerifStyle {
+    /// Any serif style.
+    Any,
+    /// No serif style fits.
+    NoFit,
+    /// Cove serifs.
+    Cove,
+    /// Obtuse cove serifs.
+    ObtuseCove,
+    /// Square cove serifs.
+    SquareCove,
+    /// Obtuse square cove serifs.
+    ObtuseSquareCove,
+    /// Square serifs.
+    Square,
+    /// Thin serifs.
+    Thin,
+    /// Oval serifs.
+    Oval,
+    /// Exaggerated serifs.
+    Exaggerated,
+    /// Triangle serifs.
+    Triangle,
+    /// Normal sans-serif.
+    NormalSans,
+    /// Obtuse sans-serif.
+    ObtuseSans,
+    /// Perpendicular sans-serif.
+    PerpendicularSans,
+    /// Flared stroke ends.
+    Flared,
+    /// Rounded stroke ends.
+    Rounded,
+    /// A serif style not listed here.
+    Other(u8),
+}
+
+impl SerifStyle {
+    /// Returns true if this style has serifs, false if it's sans-serif, or `None` if it's neither
+    /// or unknown.
+    pub fn is_serif(self) -> Option<bool> {
+        match self {
+            SerifStyle::Cove
+            | SerifStyle::ObtuseCove
+            | SerifStyle::SquareCove
+            | SerifStyle::ObtuseSquareCove
+            | SerifStyle::Square
+            | SerifStyle::Thin
+            | SerifStyle::Oval
+            | SerifStyle::Exaggerated
+            | SerifStyle::Triangle => Some(true),
+            SerifStyle::NormalSans | SerifStyle::ObtuseSans | SerifStyle::PerpendicularSans => {
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A query for faces by classification, used with `Source::select_by_classification()`.
+///
+/// This object supports a method chaining style for idiomatic initialization; e.g.
+///
+///     # use font_kit::classification::{ClassificationQuery, FamilyClass};
+///     // Serif faces, old-style ones only.
+///     println!("{:?}", ClassificationQuery::new().serif(true).family_class(FamilyClass::OldstyleSerifs));
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClassificationQuery {
+    /// If set, only faces that are known to have (or not have) serifs match.
+    pub serif: Option<bool>,
+    /// If not empty, only faces of one of these family classes match.
+    pub family_classes: Vec<FamilyClass>,
+    /// If set, matching faces are ordered by the distance of their PANOSE numbers from these.
+    pub panose: Option<Panose>,
+}
+
+impl ClassificationQuery {
+    /// Creates a query that matches any classified face.
+    #[inline]
+    pub fn new() -> ClassificationQuery {
+        ClassificationQuery::default()
+    }
+
+    /// Requires faces to have serifs, or to be sans-serif, and returns this query for method
+    /// chaining.
+    #[inline]
+    pub fn serif(&mut self, serif: bool) -> &mut ClassificationQuery {
+        self.serif = Some(serif);
+        self
+    }
+
+    /// Adds a family class to accept and returns this query for method chaining.
+    #[inline]
+    pub fn family_class(&mut self, family_class: FamilyClass) -> &mut ClassificationQuery {
+        self.family_classes.push(family_class);
+        self
+    }
+
+    /// Sets the PANOSE numbers to order matching faces by and returns this query for method
+    /// chaining.
+    #[inline]
+    pub fn panose(&mut self, panose: Panose) -> &mut ClassificationQuery {
+        self.panose = Some(panose);
+        self
+    }
+
+    /// Returns true if a face of the given classification matches this query.
+    pub fn matches(&self, classification: &Classification) -> bool {
+        if let Some(serif) = self.serif {
+            if classification.is_serif() != Some(serif) {
+                return false;
+            }
+        }
+        self.family_classes.is_empty() || self.family_classes.contains(&classification.family_class)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Classification, ClassificationQuery, FamilyClass, Panose, SerifStyle};
+
+    const OLDSTYLE_PANOSE: Panose = Panose([2, 2, 5, 2, 6, 2, 6, 2, 4, 3]);
+    const SANS_PANOSE: Panose = Panose([2, 11, 6, 4, 2, 2, 2, 2, 2, 4]);
+
+    #[test]
+    fn test_panose() {
+        assert_eq!(OLDSTYLE_PANOSE.serif_style(), Some(SerifStyle::Cove));
+        assert_eq!(SANS_PANOSE.serif_style(), Some(SerifStyle::NormalSans));
+        assert_eq!(Panose::default().serif_style(), None);
+        assert!(!OLDSTYLE_PANOSE.is_monospace());
+        assert!(Panose([2, 11, 6, 9, 2, 2, 2, 2, 2, 4]).is_monospace());
+    }
+
+    #[test]
+    fn test_panose_distance() {
+        assert_eq!(OLDSTYLE_PANOSE.distance(&OLDSTYLE_PANOSE), Some(0));
+        assert_eq!(OLDSTYLE_PANOSE.distance(&SANS_PANOSE), Some(123));
+        assert_eq!(
+            OLDSTYLE_PANOSE.distance(&Panose([2, 0, 0, 0, 0, 0, 0, 0, 0, 3])),
+            Some(0)
+        );
+        assert_eq!(OLDSTYLE_PANOSE.distance(&Panose::default()), None);
+        assert_eq!(Panose::default().distance(&Panose::default()), None);
+    }
+
+    #[test]
+    fn test_query() {
+        let oldstyle = Classification {
+            family_class: FamilyClass::OldstyleSerifs,
+            family_subclass: 2,
+            panose: OLDSTYLE_PANOSE,
+        };
+        let unclassified_sans = Classification {
+            family_class: FamilyClass::NoClassification,
+            family_subclass: 0,
+            panose: SANS_PANOSE,
+        };
+        assert!(ClassificationQuery::new().matches(&oldstyle));
+        assert!(ClassificationQuery::new().serif(true).matches(&oldstyle));
+        assert!(!ClassificationQuery::new()
+            .serif(true)
+            .matches(&unclassified_sans));
+        assert!(ClassificationQuery::new()
+            .serif(false)
+            .matches(&unclassified_sans));
+        assert!(!ClassificationQuery::new()
+            .family_class(FamilyClass::SlabSerifs)
+            .matches(&oldstyle));
+    }
+}
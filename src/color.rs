@@ -0,0 +1,51 @@
+// font-kit/src/color.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The layer structure of color glyphs.
+//!
+//! Vector renderers can draw a color glyph by filling the outline of each layer glyph in turn,
+//! from bottom to top, with the paint of that layer. Renderers that work with bitmaps can instead
+//! rasterize each layer to a separate alpha mask and recolor the masks at draw time.
+
+use pathfinder_geometry::rect::RectI;
+
+use crate::canvas::Canvas;
+
+/// One layer of a color glyph.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorLayer {
+    /// The ID of the glyph whose outline forms this layer.
+    pub glyph_id: u32,
+    /// How the outline of the layer glyph is to be filled.
+    pub paint: Paint,
+}
+
+/// How the outline of a color glyph layer is filled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Paint {
+    /// The color with the given index in the current palette of the font's `CPAL` table.
+    PaletteIndex(u16),
+    /// The foreground color of the text, as chosen by the application.
+    Foreground,
+}
+
+/// One layer of a color glyph, rasterized to an alpha mask.
+#[derive(Debug)]
+pub struct ColorLayerMask {
+    /// The ID of the glyph whose outline forms this layer.
+    pub glyph_id: u32,
+    /// How the mask is to be filled.
+    pub paint: Paint,
+    /// The pixel boundaries of the mask, relative to the origin of the glyph, with the origin of
+    /// the coordinate space at the top left.
+    pub bounds: RectI,
+    /// The coverage of the layer, in `Format::A8`, with the same size as `bounds`.
+    pub canvas: Canvas,
+}
@@ -32,6 +32,7 @@ macro_rules! impl_display {
 
 /// Reasons why a loader might fail to load a font.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum FontLoadingError {
     /// The data was of a format the loader didn't recognize.
     UnknownFormat,
@@ -45,6 +46,9 @@ pub enum FontLoadingError {
     /// Attempted to load a font from the filesystem, but there is no filesystem (e.g. in
     /// WebAssembly).
     NoFilesystem,
+    /// Attempted to load a font from a native handle created for a different loader or platform,
+    /// or one that refers to a font that is no longer installed.
+    UnsupportedHandle,
     /// A disk or similar I/O error occurred while attempting to load the font.
     Io(io::Error),
 }
@@ -56,6 +60,7 @@ impl_display! { FontLoadingError, {
         NoSuchFontInCollection => "no such font in the collection",
         Parse => "parse error",
         NoFilesystem => "no filesystem present",
+        UnsupportedHandle => "handle not supported by this loader",
         Io(e) => format!("I/O error: {}", e),
     }
 }
@@ -68,18 +73,29 @@ impl From<io::Error> for FontLoadingError {
 
 /// Reasons why a font might fail to load a glyph.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum GlyphLoadingError {
     /// The font didn't contain a glyph with that ID.
     NoSuchGlyph,
+    /// The glyph has no outline, e.g. because the font only has a bitmap for it.
+    NoOutline,
     /// A platform function returned an error.
     PlatformError,
+    /// Rasterization was stopped because the `CancellationToken` of its `RasterizationBudget` was
+    /// cancelled.
+    Cancelled,
+    /// Rasterization was stopped because the deadline of its `RasterizationBudget` passed.
+    BudgetExceeded,
 }
 
 impl Error for GlyphLoadingError {}
 
 impl_display! { GlyphLoadingError, {
         NoSuchGlyph => "no such glyph",
+        NoOutline => "glyph has no outline",
         PlatformError => "platform error",
+        Cancelled => "rasterization cancelled",
+        BudgetExceeded => "rasterization budget exceeded",
     }
 }
 
@@ -0,0 +1,148 @@
+// font-kit/src/gamma.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Gamma and contrast correction of rasterized glyph coverage.
+//!
+//! Rasterizers compute the fraction of each pixel covered by a glyph. Blending the text color
+//! with that coverage in a gamma-encoded (e.g. sRGB) framebuffer makes dark text on a light
+//! background look heavier, and light text on a dark background lighter, than blending in linear
+//! space would. Browsers correct for this by adjusting the coverage according to the luminance of
+//! the text color before blending, which is what `GammaLut` does. See
+//! `Canvas::apply_gamma_correction()`.
+
+// The number of text luminance levels that get a table of their own.
+const LUMINANCE_LEVEL_COUNT: usize = 8;
+
+/// Lookup tables that correct glyph coverage for gamma and contrast, depending on the luminance
+/// of the text color.
+///
+/// This follows the approach of Skia, as used by Chrome and Firefox: the coverage is adjusted so
+/// that blending the text color in gamma-encoded space approximates blending in linear space
+/// against a background of the opposite luminance, then the contrast is boosted.
+#[derive(Clone, Debug)]
+pub struct GammaLut {
+    tables: [[u8; 256]; LUMINANCE_LEVEL_COUNT],
+}
+
+impl GammaLut {
+    /// Builds the lookup tables for the given contrast and target gamma.
+    ///
+    /// `contrast` ranges from 0.0, which leaves the coverage as is, to 1.0. `gamma` is the gamma
+    /// of the framebuffer the text is blended into: 1.0 for linear framebuffers, and about 2.2
+    /// for sRGB. Skia's defaults are a contrast of 0.2 and a gamma of 1.2.
+    pub fn new(contrast: f32, gamma: f32) -> GammaLut {
+        let mut tables = [[0; 256]; LUMINANCE_LEVEL_COUNT];
+        for (level, table) in tables.iter_mut().enumerate() {
+            let luminance = level as f32 / (LUMINANCE_LEVEL_COUNT - 1) as f32;
+            build_table(table, luminance, contrast, gamma);
+        }
+        GammaLut { tables }
+    }
+
+    /// Returns true if and only if these tables leave coverage unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.tables
+            .iter()
+            .all(|table| (0..256).all(|value| table[value] as usize == value))
+    }
+
+    /// Returns the corrected coverage for text with the given luminance, from 0 to 255.
+    #[inline]
+    pub fn correct(&self, coverage: u8, luminance: u8) -> u8 {
+        self.table(luminance)[coverage as usize]
+    }
+
+    /// Corrects the coverage values in place for text with the given luminance, from 0 to 255.
+    pub fn correct_in_place(&self, coverage: &mut [u8], luminance: u8) {
+        let table = self.table(luminance);
+        for value in coverage {
+            *value = table[*value as usize];
+        }
+    }
+
+    #[inline]
+    fn table(&self, luminance: u8) -> &[u8; 256] {
+        &self.tables[luminance as usize * LUMINANCE_LEVEL_COUNT / 256]
+    }
+}
+
+impl Default for GammaLut {
+    /// Returns tables with Skia's default contrast of 0.2 and gamma of 1.2.
+    #[inline]
+    fn default() -> GammaLut {
+        GammaLut::new(0.2, 1.2)
+    }
+}
+
+/// Returns the luminance of the given sRGB color, from 0 to 255.
+#[inline]
+pub fn luminance(color: [u8; 3]) -> u8 {
+    // Rec. 709 weights, in 8.8 fixed point.
+    ((color[0] as u32 * 54 + color[1] as u32 * 183 + color[2] as u32 * 19) >> 8) as u8
+}
+
+fn build_table(table: &mut [u8; 256], luminance: f32, contrast: f32, gamma: f32) {
+    let linear_src = luminance.powf(gamma);
+    // Assume the background has the opposite luminance, since that's where correction matters
+    // most.
+    let linear_dst = 1.0 - linear_src;
+    let dst = linear_dst.powf(gamma.recip());
+    // Light text on a dark background needs less contrast than dark text on a light background.
+    let adjusted_contrast = contrast * linear_dst;
+
+    for (coverage, value) in table.iter_mut().enumerate() {
+        let src_alpha = coverage as f32 / 255.0;
+        let src_alpha = src_alpha + (1.0 - src_alpha) * adjusted_contrast * src_alpha;
+        let linear_out = linear_src * src_alpha + linear_dst * (1.0 - src_alpha);
+        let out = linear_out.powf(gamma.recip());
+        // Solve `out = luminance * alpha + dst * (1 - alpha)` for the alpha that produces the
+        // same result when blending in gamma-encoded space.
+        let alpha = if (luminance - dst).abs() > 1.0 / 256.0 {
+            (out - dst) / (luminance - dst)
+        } else {
+            src_alpha
+        };
+        *value = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{luminance, GammaLut};
+
+    #[test]
+    fn test_identity() {
+        assert!(GammaLut::new(0.0, 1.0).is_identity());
+        assert!(!GammaLut::default().is_identity());
+    }
+
+    #[test]
+    fn test_correct() {
+        let gamma_lut = GammaLut::new(0.0, 2.2);
+        for &luminance in &[0, 255] {
+            assert_eq!(gamma_lut.correct(0, luminance), 0);
+            assert_eq!(gamma_lut.correct(255, luminance), 255);
+        }
+        // Dark text gets thinner and light text gets bolder.
+        assert!(gamma_lut.correct(128, 0) < 128);
+        assert!(gamma_lut.correct(128, 255) > 128);
+
+        let mut coverage = [0, 128, 255];
+        gamma_lut.correct_in_place(&mut coverage, 0);
+        assert_eq!(coverage, [0, gamma_lut.correct(128, 0), 255]);
+    }
+
+    #[test]
+    fn test_luminance() {
+        assert_eq!(luminance([0, 0, 0]), 0);
+        assert_eq!(luminance([255, 255, 255]), 255);
+        assert!(luminance([0, 255, 0]) > luminance([255, 0, 0]));
+    }
+}
@@ -10,22 +10,44 @@
 
 //! Encapsulates the information needed to locate and open a font.
 //!
-//! This is either the path to the font or the raw in-memory font data.
+//! This is either the path to the font, the raw in-memory font data, or a reference to the font
+//! understood by the platform font APIs.
 //!
 //! To open the font referenced by a handle, use a loader.
 
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+use core_text::font_descriptor::CTFontDescriptor;
+#[cfg(target_family = "windows")]
+use dwrote::FontFace as DWriteFontFace;
+#[cfg(target_family = "windows")]
+use dwrote::FontFile as DWriteFontFile;
+#[cfg(any(target_os = "macos", target_os = "ios", target_family = "windows"))]
+use std::fmt::{self, Debug, Formatter};
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_family = "windows",
+    target_arch = "wasm32"
+)))]
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+#[cfg(target_family = "windows")]
+use winapi::um::dwrite::DWRITE_FONT_SIMULATIONS;
 
 use crate::error::FontLoadingError;
 use crate::font::Font;
 
 /// Encapsulates the information needed to locate and open a font.
 ///
-/// This is either the path to the font or the raw in-memory font data.
+/// This is either the path to the font, the raw in-memory font data, or a reference to the font
+/// understood by the platform font APIs.
 ///
 /// To open the font referenced by a handle, use a loader.
+///
+/// More kinds of handles may be added in the future, so matches on this enum need a wildcard arm.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Handle {
     /// A font on disk referenced by a path.
     Path {
@@ -45,6 +67,59 @@ pub enum Handle {
         /// If the memory consists of a single font, this value will be 0.
         font_index: u32,
     },
+    /// A font known to the platform font APIs, which may have neither a path nor data that can be
+    /// cheaply copied (for example, fonts activated on demand or downloaded from the cloud).
+    ///
+    /// Only the loader for the platform that created the reference can open it.
+    Native {
+        /// The platform's reference to the font.
+        handle: NativeHandle,
+    },
+}
+
+/// A reference to a font understood by the platform font APIs.
+///
+/// On macOS and iOS, this is a Core Text font descriptor. On Windows, this is a DirectWrite font
+/// file, the index of the face within it, and the styles DirectWrite simulates for it, which
+/// together identify the exact font face whether or not it's in the system font collection. On
+/// other platforms, this is a Fontconfig pattern.
+#[derive(Clone)]
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "ios", target_family = "windows")),
+    derive(Debug)
+)]
+pub struct NativeHandle {
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    core_text_font_descriptor: CTFontDescriptor,
+    #[cfg(target_family = "windows")]
+    dwrite_font_file: DWriteFontFile,
+    #[cfg(target_family = "windows")]
+    face_index: u32,
+    #[cfg(target_family = "windows")]
+    simulations: DWRITE_FONT_SIMULATIONS,
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows",
+        target_arch = "wasm32"
+    )))]
+    fontconfig_pattern: String,
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows",
+        target_arch = "wasm32"
+    )))]
+    path: PathBuf,
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows",
+        target_arch = "wasm32"
+    )))]
+    font_index: u32,
+    #[cfg(target_arch = "wasm32")]
+    _private: (),
 }
 
 impl Handle {
@@ -66,9 +141,239 @@ impl Handle {
         Handle::Memory { bytes, font_index }
     }
 
+    /// Creates a new handle from a platform font reference.
+    #[inline]
+    pub fn from_native(handle: NativeHandle) -> Handle {
+        Handle::Native { handle }
+    }
+
     /// A convenience method to load this handle with the default loader, producing a Font.
     #[inline]
     pub fn load(&self) -> Result<Font, FontLoadingError> {
         Font::from_handle(self)
     }
 }
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+impl NativeHandle {
+    /// Creates a reference to the font matching a Core Text font descriptor.
+    ///
+    /// This function is only available on the Core Text backend.
+    #[inline]
+    pub fn from_core_text_font_descriptor(descriptor: CTFontDescriptor) -> NativeHandle {
+        NativeHandle {
+            core_text_font_descriptor: descriptor,
+        }
+    }
+
+    /// Returns the Core Text font descriptor of the referenced font.
+    ///
+    /// This function is only available on the Core Text backend.
+    #[inline]
+    pub fn core_text_font_descriptor(&self) -> &CTFontDescriptor {
+        &self.core_text_font_descriptor
+    }
+}
+
+// Core Text font descriptors are immutable, and Core Foundation reference counting is atomic.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+unsafe impl Send for NativeHandle {}
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+unsafe impl Sync for NativeHandle {}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+impl Debug for NativeHandle {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("NativeHandle")
+            .field(
+                "postscript_name",
+                &self.core_text_font_descriptor.font_name(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl NativeHandle {
+    /// Creates a reference to a DirectWrite font face.
+    ///
+    /// Returns `None` if the face isn't backed by exactly one font file.
+    ///
+    /// This function is only available on the DirectWrite backend.
+    pub fn from_dwrite_font_face(dwrite_font_face: &DWriteFontFace) -> Option<NativeHandle> {
+        let mut files = dwrite_font_face.get_files();
+        if files.len() != 1 {
+            return None;
+        }
+        let (face_index, simulations) = unsafe {
+            let face = &*dwrite_font_face.as_ptr();
+            (face.GetIndex(), face.GetSimulations())
+        };
+        Some(NativeHandle {
+            dwrite_font_file: files.remove(0),
+            face_index,
+            simulations,
+        })
+    }
+
+    /// Creates the referenced DirectWrite font face, with the same simulations as the face this
+    /// reference was created from.
+    ///
+    /// This function is only available on the DirectWrite backend.
+    pub fn dwrite_font_face(&self) -> Option<DWriteFontFace> {
+        self.dwrite_font_file
+            .create_face(self.face_index, self.simulations)
+            .ok()
+    }
+
+    /// Returns the font file that contains the referenced face.
+    ///
+    /// This function is only available on the DirectWrite backend.
+    #[inline]
+    pub fn dwrite_font_file(&self) -> &DWriteFontFile {
+        &self.dwrite_font_file
+    }
+}
+
+// DirectWrite objects created by the shared factory are free-threaded.
+#[cfg(target_family = "windows")]
+unsafe impl Send for NativeHandle {}
+#[cfg(target_family = "windows")]
+unsafe impl Sync for NativeHandle {}
+
+#[cfg(target_family = "windows")]
+impl Debug for NativeHandle {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("NativeHandle")
+            .field("face_index", &self.face_index)
+            .field("simulations", &self.simulations)
+            .finish()
+    }
+}
+
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_family = "windows",
+    target_arch = "wasm32"
+)))]
+impl NativeHandle {
+    /// Creates a reference to the font that a Fontconfig pattern describes, such as one printed
+    /// by `fc-match` or `fc-list`, e.g. `DejaVu Sans:style=Book:file=/usr/share/fonts/DejaVuSans.ttf`.
+    ///
+    /// The pattern must name the font file with its `file` element, and may give the index of
+    /// the font within the file with its `index` element. Returns `None` if it has no `file`.
+    ///
+    /// This function is only available on the Fontconfig backend.
+    pub fn from_fontconfig_pattern(pattern: &str) -> Option<NativeHandle> {
+        let (path, font_index) = fontconfig::file_and_index(pattern)?;
+        Some(NativeHandle {
+            fontconfig_pattern: pattern.to_owned(),
+            path,
+            font_index,
+        })
+    }
+
+    /// Returns the Fontconfig pattern this reference was created from.
+    ///
+    /// This function is only available on the Fontconfig backend.
+    #[inline]
+    pub fn fontconfig_pattern(&self) -> &str {
+        &self.fontconfig_pattern
+    }
+
+    /// Returns the path of the referenced font file.
+    ///
+    /// This function is only available on the Fontconfig backend.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the index of the referenced font, if the file is a collection.
+    ///
+    /// This function is only available on the Fontconfig backend.
+    #[inline]
+    pub fn font_index(&self) -> u32 {
+        self.font_index
+    }
+}
+
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_family = "windows",
+    target_arch = "wasm32"
+)))]
+mod fontconfig {
+    use fontconfig_sys as ffi;
+    use fontconfig_sys::ffi_dispatch;
+
+    #[cfg(feature = "source-fontconfig-dlopen")]
+    use ffi::statics::LIB;
+    #[cfg(not(feature = "source-fontconfig-dlopen"))]
+    use ffi::*;
+
+    use std::ffi::{CStr, CString, OsStr};
+    use std::os::raw::{c_char, c_uchar};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+    use std::ptr;
+
+    // Parses the pattern with `FcNameParse` and returns its `file` and `index` elements.
+    pub(super) fn file_and_index(pattern: &str) -> Option<(PathBuf, u32)> {
+        let c_pattern = CString::new(pattern).ok()?;
+        unsafe {
+            let pattern = ffi_dispatch!(
+                feature = "source-fontconfig-dlopen",
+                LIB,
+                FcNameParse,
+                c_pattern.as_ptr() as *mut c_uchar
+            );
+            if pattern.is_null() {
+                return None;
+            }
+
+            let mut file = ptr::null_mut();
+            let file_result = ffi_dispatch!(
+                feature = "source-fontconfig-dlopen",
+                LIB,
+                FcPatternGetString,
+                pattern,
+                b"file\0".as_ptr() as *const c_char,
+                0,
+                &mut file
+            );
+            let path = if file_result == ffi::FcResultMatch && !file.is_null() {
+                let file = CStr::from_ptr(file as *const c_char);
+                Some(PathBuf::from(OsStr::from_bytes(file.to_bytes())))
+            } else {
+                None
+            };
+
+            let mut index = 0;
+            let index_result = ffi_dispatch!(
+                feature = "source-fontconfig-dlopen",
+                LIB,
+                FcPatternGetInteger,
+                pattern,
+                b"index\0".as_ptr() as *const c_char,
+                0,
+                &mut index
+            );
+            let font_index = if index_result == ffi::FcResultMatch {
+                index.max(0) as u32
+            } else {
+                0
+            };
+
+            ffi_dispatch!(
+                feature = "source-fontconfig-dlopen",
+                LIB,
+                FcPatternDestroy,
+                pattern
+            );
+            path.map(|path| (path, font_index))
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// font-kit/src/layout.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Glyph properties used for text layout: glyph classes and ligature carets from the OpenType
+//! `GDEF` table, and the displacement of slanted glyphs, from the italic angle in the `post`
+//! table.
+//!
+//! These are useful for positioning marks and placing carets inside ligatures and italic text
+//! without a full shaping engine.
+
+/// The role of a glyph in OpenType layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GlyphClass {
+    /// A base glyph: a single character, spacing glyph.
+    Base,
+    /// A ligature glyph: a multiple character, spacing glyph.
+    Ligature,
+    /// A mark glyph: a non-spacing combining glyph.
+    Mark,
+    /// A component glyph: part of a single character, spacing glyph.
+    Component,
+}
+
+/// The position of a caret between the components of a ligature glyph.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LigatureCaret {
+    /// The caret is at this X coordinate (or Y coordinate, for vertical text), in font units.
+    Coordinate(f32),
+    /// The caret is at the X coordinate (or Y coordinate, for vertical text) of this point in the
+    /// glyph outline, after hinting.
+    ContourPoint(u16),
+}
+
+/// Returns the horizontal distance by which a slant of `italic_angle` degrees displaces a point
+/// at `height` above the baseline.
+///
+/// The angle is measured counterclockwise from the vertical, as in `Loader::italic_angle()`, so
+/// text leaning to the right yields a positive translation for points above the baseline.
+#[inline]
+pub fn slant_translation(italic_angle: f32, height: f32) -> f32 {
+    -height * italic_angle.to_radians().tan()
+}
+
+#[cfg(test)]
+mod test {
+    use super::slant_translation;
+
+    #[test]
+    fn test_slant_translation() {
+        assert_eq!(slant_translation(0.0, 500.0), 0.0);
+        assert!((slant_translation(-45.0, 500.0) - 500.0).abs() < 0.001);
+        assert!((slant_translation(-45.0, -200.0) + 200.0).abs() < 0.001);
+        assert!((slant_translation(-12.0, 100.0) - 21.2557).abs() < 0.001);
+    }
+}
@@ -125,14 +125,19 @@
 extern crate bitflags;
 
 pub mod canvas;
+pub mod checksum;
+pub mod classification;
+pub mod color;
 pub mod error;
 pub mod family;
 pub mod family_handle;
 pub mod family_name;
 pub mod file_type;
 pub mod font;
+pub mod gamma;
 pub mod handle;
 pub mod hinting;
+pub mod layout;
 pub mod loader;
 pub mod loaders;
 pub mod metrics;
@@ -143,6 +148,12 @@ pub mod properties;
 pub mod source;
 #[cfg(feature = "source")]
 pub mod sources;
+#[cfg(feature = "source")]
+pub mod substitution;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 mod matching;
+mod rasterizer;
+mod tables;
 mod utils;
@@ -15,22 +15,34 @@ use log::warn;
 use pathfinder_geometry::rect::{RectF, RectI};
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::Vector2F;
+use std::convert::TryFrom;
 use std::sync::Arc;
 
-use crate::canvas::{Canvas, RasterizationOptions};
+use crate::canvas::{Canvas, Format, GlyphRasterInfo, GlyphRepresentation};
+use crate::canvas::{RasterizationBudget, RasterizationOptions, Synthesis};
+use crate::checksum::{self, ChecksumReport};
+use crate::classification::Classification;
+use crate::color::{ColorLayer, ColorLayerMask, Paint};
 use crate::error::{FontLoadingError, GlyphLoadingError};
 use crate::file_type::FileType;
-use crate::handle::Handle;
+use crate::handle::{Handle, NativeHandle};
 use crate::hinting::HintingOptions;
-use crate::metrics::Metrics;
-use crate::outline::OutlineSink;
-use crate::properties::Properties;
+use crate::layout::{GlyphClass, LigatureCaret};
+use crate::metrics::{Metrics, RoundingMode, ScaledMetrics, TextSize};
+use crate::outline::{OutlineBuilder, OutlineSink};
+use crate::properties::{Features, Properties};
+use crate::rasterizer;
+use crate::tables::{self, colr, gdef, gpos, layout, loca, math, os2, post};
 
 #[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 
+/// The ID of the `.notdef` glyph, which fonts draw for characters they have no glyph for, often as
+/// a box (known as "tofu").
+pub const NOTDEF_GLYPH_ID: u32 = 0;
+
 /// Provides a common interface to the platform-specific API that loads, parses, and rasterizes
 /// fonts.
 pub trait Loader: Clone + Sized {
@@ -79,9 +91,18 @@ pub trait Loader: Clone + Sized {
             } => Self::from_path(path, font_index),
             #[cfg(target_arch = "wasm32")]
             Handle::Path { .. } => Err(FontLoadingError::NoFilesystem),
+            Handle::Native { ref handle } => Self::from_native_handle(handle),
         }
     }
 
+    /// Loads the font that a platform font reference refers to.
+    ///
+    /// Only loaders built on the platform font APIs support this. The default implementation
+    /// returns `FontLoadingError::UnsupportedHandle`.
+    fn from_native_handle(_: &NativeHandle) -> Result<Self, FontLoadingError> {
+        Err(FontLoadingError::UnsupportedHandle)
+    }
+
     /// Determines whether a blob of raw font data represents a supported font, and, if so, what
     /// type of font it is.
     fn analyze_bytes(font_data: Arc<Vec<u8>>) -> Result<FileType, FontLoadingError>;
@@ -130,6 +151,36 @@ pub trait Loader: Clone + Sized {
     /// use cases like "what does character X look like on its own".
     fn glyph_for_char(&self, character: char) -> Option<u32>;
 
+    /// Returns true if and only if the font maps the given character to a glyph other than
+    /// `.notdef`.
+    ///
+    /// Some loaders return the `.notdef` glyph from `glyph_for_char()` for characters that the
+    /// font doesn't map, rather than `None`, so use this to detect missing glyphs.
+    #[inline]
+    fn has_glyph_for_char(&self, character: char) -> bool {
+        matches!(self.glyph_for_char(character), Some(glyph_id) if glyph_id != NOTDEF_GLYPH_ID)
+    }
+
+    /// Returns true if the `.notdef` glyph of the font has no outline, so that missing characters
+    /// are invisible rather than drawn as boxes.
+    ///
+    /// For TrueType fonts, this only reads the `loca` table; otherwise, the outline is loaded.
+    fn notdef_is_blank(&self) -> Result<bool, GlyphLoadingError> {
+        let head_table = self.load_font_table(tables::TABLE_TAG_HEAD);
+        let loca_table = self.load_font_table(tables::TABLE_TAG_LOCA);
+        if let (Some(head_table), Some(loca_table)) = (head_table, loca_table) {
+            if let Some(is_empty) =
+                loca::glyph_is_empty(&head_table, &loca_table, NOTDEF_GLYPH_ID as u16)
+            {
+                return Ok(is_empty);
+            }
+        }
+
+        let mut outline_builder = OutlineBuilder::new();
+        self.outline(NOTDEF_GLYPH_ID, HintingOptions::None, &mut outline_builder)?;
+        Ok(outline_builder.into_outline().contours.is_empty())
+    }
+
     /// Returns the glyph ID for the specified glyph name.
     #[inline]
     fn glyph_by_name(&self, _name: &str) -> Option<u32> {
@@ -160,12 +211,48 @@ pub trait Loader: Clone + Sized {
     /// units.
     fn advance(&self, glyph_id: u32) -> Result<Vector2F, GlyphLoadingError>;
 
+    /// Returns the distance from the origin of the glyph with the given ID to the next glyph, in
+    /// pixels at the given point size and resolution in dots per inch, rounded according to
+    /// `rounding_mode`.
+    ///
+    /// The advance is scaled with `Metrics::pixels_per_font_unit()`, so it's consistent with the
+    /// other scaling helpers.
+    fn advance_scaled(
+        &self,
+        glyph_id: u32,
+        point_size: f32,
+        dpi: f32,
+        rounding_mode: RoundingMode,
+    ) -> Result<Vector2F, GlyphLoadingError> {
+        let advance = self.advance(glyph_id)?;
+        let scale = self.metrics().pixels_per_font_unit(point_size, dpi);
+        Ok(rounding_mode.round_vector(advance * scale))
+    }
+
+    /// Returns the distance from the origin of the glyph with the given ID to the next glyph, in
+    /// pixels at the given font size, rounded according to `rounding_mode`.
+    #[inline]
+    fn advance_for_size(
+        &self,
+        glyph_id: u32,
+        size: TextSize,
+        rounding_mode: RoundingMode,
+    ) -> Result<Vector2F, GlyphLoadingError> {
+        self.advance_scaled(glyph_id, size.pt, size.dpi, rounding_mode)
+    }
+
     /// Returns the amount that the given glyph should be displaced from the origin.
     fn origin(&self, glyph_id: u32) -> Result<Vector2F, GlyphLoadingError>;
 
     /// Retrieves various metrics that apply to the entire font.
     fn metrics(&self) -> Metrics;
 
+    /// Retrieves the metrics of the font scaled to device pixels at the given font size.
+    #[inline]
+    fn scaled_metrics(&self, size: TextSize) -> ScaledMetrics {
+        self.metrics().scale_for_size(size)
+    }
+
     /// Returns a handle to this font, if possible.
     ///
     /// This is useful if you want to open the font with a different loader.
@@ -175,6 +262,15 @@ pub trait Loader: Clone + Sized {
             .map(|font_data| Handle::from_memory(font_data, 0))
     }
 
+    /// Returns the index of this font in the data returned by `copy_font_data()`, if that data
+    /// is a collection (`.ttc`/`.otc`/etc.).
+    ///
+    /// The default implementation returns 0, for loaders whose font data is never a collection.
+    #[inline]
+    fn font_index(&self) -> u32 {
+        0
+    }
+
     /// Attempts to return the raw font data (contents of the font file).
     ///
     /// If this font is a member of a collection, this function returns the data for the entire
@@ -202,8 +298,12 @@ pub trait Loader: Clone + Sized {
         point_size: f32,
         transform: Transform2F,
         _: HintingOptions,
-        _: RasterizationOptions,
+        rasterization_options: RasterizationOptions,
     ) -> Result<RectI, GlyphLoadingError> {
+        if rasterization_options == RasterizationOptions::Deterministic {
+            return rasterizer::raster_bounds(self, glyph_id, point_size, transform);
+        }
+
         let typographic_bounds = self.typographic_bounds(glyph_id)?;
         let typographic_raster_bounds =
             typographic_bounds * (point_size / self.metrics().units_per_em as f32);
@@ -217,6 +317,45 @@ pub trait Loader: Clone + Sized {
         Ok((transform * typographic_raster_bounds).round_out().to_i32())
     }
 
+    /// Returns the pixel boundaries that the glyph will take up when rendered at the given font
+    /// size, like `raster_bounds()`.
+    #[inline]
+    fn raster_bounds_for_size(
+        &self,
+        glyph_id: u32,
+        size: TextSize,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<RectI, GlyphLoadingError> {
+        self.raster_bounds(
+            glyph_id,
+            size.px_per_em(),
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+    }
+
+    /// Returns the pixel boundaries that the `.notdef` glyph will take up when rendered at the
+    /// given size and transform. See `raster_bounds()`.
+    #[inline]
+    fn notdef_raster_bounds(
+        &self,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<RectI, GlyphLoadingError> {
+        self.raster_bounds(
+            NOTDEF_GLYPH_ID,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+    }
+
     /// Rasterizes a glyph to a canvas with the given size and transform.
     ///
     /// Format conversion will be performed if the canvas format does not match the rasterization
@@ -236,6 +375,143 @@ pub trait Loader: Clone + Sized {
         rasterization_options: RasterizationOptions,
     ) -> Result<(), GlyphLoadingError>;
 
+    /// Rasterizes the `.notdef` glyph to a canvas with the given size and transform, e.g. to draw
+    /// a placeholder for a character that no font has a glyph for. See `rasterize_glyph()`.
+    #[inline]
+    fn rasterize_notdef(
+        &self,
+        canvas: &mut Canvas,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<(), GlyphLoadingError> {
+        self.rasterize_glyph(
+            canvas,
+            NOTDEF_GLYPH_ID,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+    }
+
+    /// Rasterizes a glyph to a canvas at the given font size, like `rasterize_glyph()`.
+    #[inline]
+    fn rasterize_glyph_for_size(
+        &self,
+        canvas: &mut Canvas,
+        glyph_id: u32,
+        size: TextSize,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<(), GlyphLoadingError> {
+        self.rasterize_glyph(
+            canvas,
+            glyph_id,
+            size.px_per_em(),
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+    }
+
+    /// Rasterizes a glyph like `rasterize_glyph()`, and reports which representation of the glyph
+    /// the rasterizer drew and which styles it synthesized.
+    ///
+    /// Callers can use this to position bitmap glyphs, which are scaled from the nearest strike,
+    /// and to avoid tinting color glyphs. The default implementation is for loaders whose
+    /// rasterizers only draw outlines.
+    fn rasterize_glyph_with_info(
+        &self,
+        canvas: &mut Canvas,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<GlyphRasterInfo, GlyphLoadingError> {
+        self.rasterize_glyph(
+            canvas,
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )?;
+        Ok(outline_raster_info(
+            self,
+            hinting_options,
+            rasterization_options,
+        ))
+    }
+
+    /// Returns the pixel boundaries that the glyph will take up when rendered, like
+    /// `raster_bounds()`, along with the representation of the glyph that the rasterizer draws.
+    /// See `rasterize_glyph_with_info()`.
+    fn raster_bounds_with_info(
+        &self,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<(RectI, GlyphRasterInfo), GlyphLoadingError> {
+        let bounds = self.raster_bounds(
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )?;
+        Ok((
+            bounds,
+            outline_raster_info(self, hinting_options, rasterization_options),
+        ))
+    }
+
+    /// Returns the styles that this loader synthesizes for the font, such as the bold and oblique
+    /// simulations of DirectWrite.
+    #[inline]
+    fn synthesis(&self) -> Synthesis {
+        Synthesis::default()
+    }
+
+    /// Rasterizes a glyph like `rasterize_glyph()`, but stops once the given budget is exhausted,
+    /// returning `GlyphLoadingError::Cancelled` or `GlyphLoadingError::BudgetExceeded`.
+    ///
+    /// The budget is enforced while rasterizing with `RasterizationOptions::Deterministic`. Other
+    /// options use the platform rasterizer, which can't be interrupted, so the budget is only
+    /// checked before rasterization starts.
+    #[allow(clippy::too_many_arguments)]
+    fn rasterize_glyph_with_budget(
+        &self,
+        canvas: &mut Canvas,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+        budget: &RasterizationBudget,
+    ) -> Result<(), GlyphLoadingError> {
+        if rasterization_options == RasterizationOptions::Deterministic {
+            return rasterizer::rasterize_glyph_with_budget(
+                self, canvas, glyph_id, point_size, transform, budget,
+            );
+        }
+
+        budget.check()?;
+        self.rasterize_glyph(
+            canvas,
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+    }
+
     /// Get font fallback results for the given text and locale.
     ///
     /// The `locale` argument is a language tag such as `"en-US"` or `"zh-Hans-CN"`.
@@ -243,6 +519,277 @@ pub trait Loader: Clone + Sized {
 
     /// Returns the OpenType font table with the given tag, if the table exists.
     fn load_font_table(&self, table_tag: u32) -> Option<Box<[u8]>>;
+
+    /// Verifies the table checksums and the `head` checksum adjustment of the font data.
+    ///
+    /// This is a quick integrity check for font data that has been subset, converted, or
+    /// otherwise rewritten. Returns `None` if the font data isn't available (see
+    /// `copy_font_data()`) or its table directory can't be read.
+    fn verify_checksums(&self) -> Option<ChecksumReport> {
+        let font_data = self.copy_font_data()?;
+        checksum::verify_checksums(&font_data, self.font_index())
+    }
+
+    /// Returns the IBM family class and PANOSE classification of the font, from its `OS/2` table.
+    ///
+    /// Returns `None` if the font has no `OS/2` table, as is the case for many fonts made for
+    /// the Mac.
+    fn classification(&self) -> Option<Classification> {
+        let table = self.load_font_table(tables::TABLE_TAG_OS2)?;
+        os2::classification(&table)
+    }
+
+    /// Returns the tags of the OpenType layout features defined in the font's `GSUB` and `GPOS`
+    /// tables, sorted and without duplicates.
+    ///
+    /// Tags are four-character codes such as `smcp`. A list of registered features can be found in
+    /// the [OpenType specification].
+    ///
+    /// [OpenType specification]: https://docs.microsoft.com/en-us/typography/opentype/spec/featurelist
+    fn feature_tags(&self) -> Vec<u32> {
+        let mut feature_tags = vec![];
+        for &table_tag in &[tables::TABLE_TAG_GSUB, tables::TABLE_TAG_GPOS] {
+            if let Some(table) = self.load_font_table(table_tag) {
+                match layout::feature_tags(&table) {
+                    Some(table_feature_tags) => feature_tags.extend(table_feature_tags),
+                    None => warn!("malformed feature list"),
+                }
+            }
+        }
+        feature_tags.sort();
+        feature_tags.dedup();
+        feature_tags
+    }
+
+    /// Returns which of the features in `Features` the font supports.
+    #[inline]
+    fn supported_features(&self) -> Features {
+        Features::from_tags(&self.feature_tags())
+    }
+
+    /// Rasterizes each layer of the color glyph with the given ID to a separate alpha mask, from
+    /// bottom to top, at the given size and transform.
+    ///
+    /// Unlike a flattened color image, the masks can be recolored at draw time, for example when
+    /// the palette or the foreground color changes. A glyph that isn't a layered color glyph
+    /// yields a single mask painted with the foreground color.
+    fn rasterize_color_layers(
+        &self,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<Vec<ColorLayerMask>, GlyphLoadingError> {
+        self.rasterize_color_layers_with_budget(
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+            &RasterizationBudget::new(),
+        )
+    }
+
+    /// Rasterizes the layers of a color glyph like `rasterize_color_layers()`, but stops once the
+    /// given budget is exhausted. See `rasterize_glyph_with_budget()`.
+    fn rasterize_color_layers_with_budget(
+        &self,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+        budget: &RasterizationBudget,
+    ) -> Result<Vec<ColorLayerMask>, GlyphLoadingError> {
+        let mut layers = self.color_layers(glyph_id);
+        if layers.is_empty() {
+            layers.push(ColorLayer {
+                glyph_id,
+                paint: Paint::Foreground,
+            });
+        }
+
+        layers
+            .into_iter()
+            .map(|layer| {
+                let bounds = self.raster_bounds(
+                    layer.glyph_id,
+                    point_size,
+                    transform,
+                    hinting_options,
+                    rasterization_options,
+                )?;
+                let mut canvas = Canvas::new(bounds.size(), Format::A8);
+                if bounds.width() > 0 && bounds.height() > 0 {
+                    self.rasterize_glyph_with_budget(
+                        &mut canvas,
+                        layer.glyph_id,
+                        point_size,
+                        Transform2F::from_translation(-bounds.origin().to_f32()) * transform,
+                        hinting_options,
+                        rasterization_options,
+                        budget,
+                    )?;
+                }
+                Ok(ColorLayerMask {
+                    glyph_id: layer.glyph_id,
+                    paint: layer.paint,
+                    bounds,
+                    canvas,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the class of the glyph with the given ID from the font's `GDEF` table, or `None`
+    /// if the font doesn't classify the glyph.
+    fn glyph_class(&self, glyph_id: u32) -> Option<GlyphClass> {
+        let table = self.load_font_table(tables::TABLE_TAG_GDEF)?;
+        gdef::glyph_class(&table, u16::try_from(glyph_id).ok()?)
+    }
+
+    /// Returns the mark attachment class of the glyph with the given ID from the font's `GDEF`
+    /// table, or `None` if the glyph has none.
+    ///
+    /// Lookups can be restricted to marks of a single attachment class.
+    fn mark_attachment_class(&self, glyph_id: u32) -> Option<u16> {
+        let table = self.load_font_table(tables::TABLE_TAG_GDEF)?;
+        gdef::mark_attachment_class(&table, u16::try_from(glyph_id).ok()?)
+    }
+
+    /// Returns the indices of the points in the outline of the glyph with the given ID to which
+    /// other glyphs attach, from the `AttachList` of the font's `GDEF` table.
+    ///
+    /// These contour points only let renderers follow the anchors when hinting moves them; the
+    /// anchors themselves are in the `GPOS` table; see `mark_attachment_offset()`. The list is
+    /// empty if the font doesn't define attachment points for the glyph.
+    fn attachment_points(&self, glyph_id: u32) -> Vec<u16> {
+        self.load_font_table(tables::TABLE_TAG_GDEF)
+            .and_then(|table| gdef::attachment_points(&table, u16::try_from(glyph_id).ok()?))
+            .unwrap_or_default()
+    }
+
+    /// Returns the offset, in font units, from the origin of the base glyph with the given ID to
+    /// the origin of the mark glyph with the given ID when the mark is attached to the base, from
+    /// the anchors in the font's `GPOS` table.
+    ///
+    /// The base may itself be a mark, for stacked marks. Only the mark-to-base and mark-to-mark
+    /// lookups of the `mark` and `mkmk` features are consulted, regardless of script and
+    /// language, and hinting adjustments to the anchors are ignored. Returns `None` if the font
+    /// doesn't attach the mark to the base.
+    fn mark_attachment_offset(&self, base_glyph_id: u32, mark_glyph_id: u32) -> Option<Vector2F> {
+        let table = self.load_font_table(tables::TABLE_TAG_GPOS)?;
+        gpos::mark_attachment_offset(
+            &table,
+            u16::try_from(base_glyph_id).ok()?,
+            u16::try_from(mark_glyph_id).ok()?,
+        )
+    }
+
+    /// Returns the positions of the carets between the components of the ligature glyph with the
+    /// given ID, from the font's `GDEF` table.
+    ///
+    /// The list is empty if the glyph isn't a ligature or the font doesn't define carets for it.
+    fn ligature_carets(&self, glyph_id: u32) -> Vec<LigatureCaret> {
+        self.load_font_table(tables::TABLE_TAG_GDEF)
+            .and_then(|table| gdef::ligature_carets(&table, u16::try_from(glyph_id).ok()?))
+            .unwrap_or_default()
+    }
+
+    /// Returns the italic angle of the font in degrees counterclockwise from the vertical, from the
+    /// font's `post` table.
+    ///
+    /// Upright fonts return zero, and fonts that lean to the right return a negative angle.
+    fn italic_angle(&self) -> f32 {
+        self.load_font_table(tables::TABLE_TAG_POST)
+            .and_then(|table| post::italic_angle(&table))
+            .unwrap_or(0.0)
+    }
+
+    /// Returns the horizontal distance, in font units, by which the font's slant displaces a point
+    /// at `height` font units above the baseline.
+    ///
+    /// This is the shift to apply to a caret drawn at that height in italic text, or to a mark
+    /// centered over a slanted glyph at that height. Upright fonts return zero.
+    #[inline]
+    fn slant_translation(&self, height: f32) -> f32 {
+        crate::layout::slant_translation(self.italic_angle(), height)
+    }
+
+    /// Returns the italics correction of the glyph with the given ID in font units, from the
+    /// font's `MATH` table.
+    ///
+    /// This is the extra space to leave after a slanted glyph when it is followed by an upright
+    /// one, and the offset of superscripts attached to it. Returns `None` if the font doesn't
+    /// specify one for the glyph.
+    fn italic_correction(&self, glyph_id: u32) -> Option<f32> {
+        let table = self.load_font_table(tables::TABLE_TAG_MATH)?;
+        math::italics_correction(&table, u16::try_from(glyph_id).ok()?).map(f32::from)
+    }
+
+    /// Returns the horizontal position, in font units, at which an accent should be attached
+    /// above the glyph with the given ID.
+    ///
+    /// This comes from the font's `MATH` table if it has one. Otherwise, it is the horizontal
+    /// center of the glyph's typographic bounds, translated by the font's slant to the top of the
+    /// glyph.
+    fn top_accent_attachment(&self, glyph_id: u32) -> Result<f32, GlyphLoadingError> {
+        if let Some(table) = self.load_font_table(tables::TABLE_TAG_MATH) {
+            let attachment = u16::try_from(glyph_id)
+                .ok()
+                .and_then(|glyph_id| math::top_accent_attachment(&table, glyph_id));
+            if let Some(attachment) = attachment {
+                return Ok(f32::from(attachment));
+            }
+        }
+        let bounds = self.typographic_bounds(glyph_id)?;
+        let center = bounds.center();
+        Ok(center.x() + self.slant_translation(bounds.max_y() - center.y()))
+    }
+
+    /// Returns the layers that make up the color glyph with the given ID, from bottom to top, as
+    /// defined in the font's `COLR` table.
+    ///
+    /// Each layer is itself a glyph, which can be outlined or rasterized as usual and then filled
+    /// with the layer's paint. If the glyph isn't a layered color glyph, the returned list is
+    /// empty.
+    fn color_layers(&self, glyph_id: u32) -> Vec<ColorLayer> {
+        let table = match self.load_font_table(tables::TABLE_TAG_COLR) {
+            None => return vec![],
+            Some(table) => table,
+        };
+        match colr::layers(&table, glyph_id) {
+            Some(layers) => layers,
+            None => {
+                warn!("malformed color table");
+                vec![]
+            }
+        }
+    }
+}
+
+// Describes a glyph that is drawn from its outline, with the styles that the loader synthesizes.
+// The deterministic rasterizer never hints.
+pub(crate) fn outline_raster_info<L>(
+    font: &L,
+    hinting_options: HintingOptions,
+    rasterization_options: RasterizationOptions,
+) -> GlyphRasterInfo
+where
+    L: Loader,
+{
+    let hinted = rasterization_options != RasterizationOptions::Deterministic
+        && hinting_options != HintingOptions::None
+        && font.supports_hinting_options(hinting_options, true);
+    GlyphRasterInfo {
+        representation: if hinted {
+            GlyphRepresentation::HintedOutline
+        } else {
+            GlyphRepresentation::Outline
+        },
+        synthesis: font.synthesis(),
+    }
 }
 
 /// The result of a fallback query.
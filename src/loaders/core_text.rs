@@ -11,6 +11,9 @@
 //! A loader that uses Apple's Core Text API to load and rasterize fonts.
 
 use byteorder::{BigEndian, ReadBytesExt};
+use core_foundation::base::TCFType;
+use core_foundation::data::{CFData, CFDataRef};
+use core_foundation::string::CFString;
 use core_graphics::base::{kCGImageAlphaPremultipliedLast, CGFloat};
 use core_graphics::color_space::CGColorSpace;
 use core_graphics::context::{CGContext, CGTextDrawingMode};
@@ -20,7 +23,8 @@ use core_graphics::geometry::{CG_AFFINE_TRANSFORM_IDENTITY, CG_ZERO_POINT, CG_ZE
 use core_graphics::path::CGPathElementType;
 use core_text;
 use core_text::font::CTFont;
-use core_text::font_descriptor::kCTFontDefaultOrientation;
+use core_text::font_descriptor::{kCTFontColorGlyphsTrait, kCTFontDefaultOrientation};
+use core_text::font_descriptor::{CTFontDescriptor, CTFontDescriptorRef};
 use core_text::font_descriptor::{SymbolicTraitAccessors, TraitAccessors};
 use log::warn;
 use pathfinder_geometry::line_segment::LineSegment2F;
@@ -29,6 +33,7 @@ use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::Vector2F;
 use pathfinder_simd::default::F32x4;
 use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::f32;
 use std::fmt::{self, Debug, Formatter};
 use std::fs::File;
@@ -37,15 +42,17 @@ use std::ops::Deref;
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::canvas::{Canvas, Format, RasterizationOptions};
+use crate::canvas::{Canvas, Format, GlyphRasterInfo, GlyphRepresentation, RasterizationOptions};
 use crate::error::{FontLoadingError, GlyphLoadingError};
 use crate::file_type::FileType;
-use crate::handle::Handle;
+use crate::handle::{Handle, NativeHandle};
 use crate::hinting::HintingOptions;
-use crate::loader::{FallbackResult, Loader};
+use crate::loader::{self, FallbackResult, Loader};
 use crate::metrics::Metrics;
 use crate::outline::OutlineSink;
 use crate::properties::{Properties, Stretch, Style, Weight};
+use crate::rasterizer;
+use crate::tables::{self, sbix};
 use crate::utils;
 
 const TTC_TAG: [u8; 4] = [b't', b't', b'c', b'f'];
@@ -58,6 +65,11 @@ const SFNT_HEX: u32 = 0x73666e74; // 'sfnt'
 #[allow(non_upper_case_globals)]
 const kCGImageAlphaOnly: u32 = 7;
 
+#[link(name = "CoreText", kind = "framework")]
+extern "C" {
+    fn CTFontManagerCreateFontDescriptorFromData(data: CFDataRef) -> CTFontDescriptorRef;
+}
+
 pub(crate) static FONT_WEIGHT_MAPPING: [f32; 9] = [-0.7, -0.5, -0.23, 0.0, 0.2, 0.3, 0.4, 0.6, 0.8];
 
 /// Core Text's representation of a font.
@@ -75,6 +87,11 @@ impl Font {
     ///
     /// If the data represents a collection (`.ttc`/`.otc`/etc.), `font_index` specifies the index
     /// of the font to load from it. If the data represents a single font, pass 0 for `font_index`.
+    ///
+    /// The font is created from a descriptor made by `CTFontManagerCreateFontDescriptorFromData`
+    /// and is never registered with the font manager, so there is nothing to unregister: it is
+    /// invisible to other processes and to font matching, and all the memory it uses is freed
+    /// when the last clone of the returned `Font` is dropped.
     pub fn from_bytes(
         mut font_data: Arc<Vec<u8>>,
         font_index: u32,
@@ -91,10 +108,7 @@ impl Font {
             font_data = Arc::new(new_font_data);
         }
 
-        let core_text_font = match core_text::font::new_from_buffer(&*font_data) {
-            Ok(ct_font) => ct_font,
-            Err(_) => return Err(FontLoadingError::Parse),
-        };
+        let core_text_font = new_core_text_font_from_data(&font_data)?;
 
         Ok(Font {
             core_text_font,
@@ -168,10 +182,8 @@ impl Font {
         if let Ok(font_count) = read_number_of_fonts_from_otc_header(&font_data) {
             return Ok(FileType::Collection(font_count));
         }
-        match core_text::font::new_from_buffer(&*font_data) {
-            Ok(_) => Ok(FileType::Single),
-            Err(_) => Err(FontLoadingError::Parse),
-        }
+        new_core_text_font_descriptor_from_data(&CFData::from_buffer(&font_data))?;
+        Ok(FileType::Single)
     }
 
     /// Determines whether a file represents a supported font, and if so, what type of font it is.
@@ -183,10 +195,8 @@ impl Font {
             return Ok(FileType::Collection(font_count));
         }
 
-        match core_text::font::new_from_buffer(&*font_data) {
-            Ok(_) => Ok(FileType::Single),
-            Err(_) => Err(FontLoadingError::Parse),
-        }
+        new_core_text_font_descriptor_from_data(&CFData::from_buffer(&font_data))?;
+        Ok(FileType::Single)
     }
 
     /// Determines whether a path points to a supported font, and if so, what type of font it is.
@@ -263,6 +273,15 @@ impl Font {
         self.core_text_font.glyph_count() as u32
     }
 
+    // Core Text doesn't report out-of-range glyphs, so they're rejected up front.
+    fn check_glyph_id(&self, glyph_id: u32) -> Result<u16, GlyphLoadingError> {
+        if glyph_id < self.glyph_count() {
+            Ok(glyph_id as u16)
+        } else {
+            Err(GlyphLoadingError::NoSuchGlyph)
+        }
+    }
+
     /// Returns the usual glyph ID for a Unicode character.
     ///
     /// Be careful with this function; typographically correct character-to-glyph mapping must be
@@ -307,15 +326,16 @@ impl Font {
     where
         S: OutlineSink,
     {
+        let glyph_id = self.check_glyph_id(glyph_id)?;
         let path = match self
             .core_text_font
-            .create_path_for_glyph(glyph_id as u16, &CG_AFFINE_TRANSFORM_IDENTITY)
+            .create_path_for_glyph(glyph_id, &CG_AFFINE_TRANSFORM_IDENTITY)
         {
             Ok(path) => path,
             Err(_) => {
                 // This will happen if the path is empty (rdar://42832439). To distinguish this
                 // case from the case in which the glyph does not exist, call another API.
-                drop(self.typographic_bounds(glyph_id)?);
+                drop(self.typographic_bounds(glyph_id as u32)?);
                 return Ok(());
             }
         };
@@ -347,9 +367,10 @@ impl Font {
 
     /// Returns the boundaries of a glyph in font units.
     pub fn typographic_bounds(&self, glyph_id: u32) -> Result<RectF, GlyphLoadingError> {
+        let glyph_id = self.check_glyph_id(glyph_id)?;
         let rect = self
             .core_text_font
-            .get_bounding_rects_for_glyphs(kCTFontDefaultOrientation, &[glyph_id as u16]);
+            .get_bounding_rects_for_glyphs(kCTFontDefaultOrientation, &[glyph_id]);
         let rect = RectF::new(
             Vector2F::new(rect.origin.x as f32, rect.origin.y as f32),
             Vector2F::new(rect.size.width as f32, rect.size.height as f32),
@@ -360,9 +381,10 @@ impl Font {
     /// Returns the distance from the origin of the glyph with the given ID to the next, in font
     /// units.
     pub fn advance(&self, glyph_id: u32) -> Result<Vector2F, GlyphLoadingError> {
-        // FIXME(pcwalton): Apple's docs don't say what happens when the glyph is out of range!
+        // Apple's docs don't say what happens when the glyph is out of range, so check first.
+        let glyph_id = self.check_glyph_id(glyph_id)?;
         unsafe {
-            let (glyph_id, mut advance) = (glyph_id as u16, CG_ZERO_SIZE);
+            let mut advance = CG_ZERO_SIZE;
             self.core_text_font.get_advances_for_glyphs(
                 kCTFontDefaultOrientation,
                 &glyph_id,
@@ -421,10 +443,17 @@ impl Font {
 
     /// Returns a handle to this font, if possible.
     ///
-    /// This is useful if you want to open the font with a different loader.
-    #[inline]
+    /// This is useful if you want to open the font with a different loader. If the font data
+    /// isn't available (for example, because the font was activated on demand), this returns a
+    /// native handle wrapping the font's descriptor, which only the Core Text loader can open.
     pub fn handle(&self) -> Option<Handle> {
-        <Self as Loader>::handle(self)
+        // FIXME(pcwalton): This doesn't handle font collections!
+        match self.font_data {
+            FontData::Memory(ref font_data) => Some(Handle::from_memory(font_data.clone(), 0)),
+            FontData::Unavailable => Some(Handle::from_native(
+                NativeHandle::from_core_text_font_descriptor(self.core_text_font.copy_descriptor()),
+            )),
+        }
     }
 
     /// Attempts to return the raw font data (contents of the font file).
@@ -471,6 +500,7 @@ impl Font {
     ///
     /// TODO(pcwalton): This is woefully incomplete. See WebRender's code for a more complete
     /// implementation.
+    #[inline]
     pub fn rasterize_glyph(
         &self,
         canvas: &mut Canvas,
@@ -480,8 +510,36 @@ impl Font {
         hinting_options: HintingOptions,
         rasterization_options: RasterizationOptions,
     ) -> Result<(), GlyphLoadingError> {
+        self.rasterize_glyph_with_info(
+            canvas,
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+        .map(|_| ())
+    }
+
+    /// Rasterizes a glyph like `rasterize_glyph()`, and reports which representation of the glyph
+    /// Core Text drew and which styles it synthesized.
+    pub fn rasterize_glyph_with_info(
+        &self,
+        canvas: &mut Canvas,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<GlyphRasterInfo, GlyphLoadingError> {
+        let info = self.raster_info(glyph_id, point_size, hinting_options, rasterization_options);
         if canvas.size.x() == 0 || canvas.size.y() == 0 {
-            return Ok(());
+            return Ok(info);
+        }
+
+        if rasterization_options == RasterizationOptions::Deterministic {
+            rasterizer::rasterize_glyph(self, canvas, glyph_id, point_size, transform)?;
+            return Ok(info);
         }
 
         let (cg_color_space, cg_image_format) =
@@ -493,7 +551,7 @@ impl Font {
                     // FIXME(pcwalton): Could improve this by only allocating a canvas with a tight
                     // bounding rect and blitting only that part.
                     let mut temp_canvas = Canvas::new(canvas.size, Format::Rgba32);
-                    self.rasterize_glyph(
+                    self.rasterize_glyph_with_info(
                         &mut temp_canvas,
                         glyph_id,
                         point_size,
@@ -502,7 +560,7 @@ impl Font {
                         rasterization_options,
                     )?;
                     canvas.blit_from_canvas(&temp_canvas);
-                    return Ok(());
+                    return Ok(info);
                 }
                 Some(cg_color_space_and_format) => cg_color_space_and_format,
             };
@@ -527,18 +585,16 @@ impl Font {
         let core_graphics_size = CGSize::new(canvas.size.x() as f64, canvas.size.y() as f64);
         core_graphics_context.fill_rect(CGRect::new(&CG_ZERO_POINT, &core_graphics_size));
 
-        match rasterization_options {
-            RasterizationOptions::Bilevel => {
-                core_graphics_context.set_allows_font_smoothing(false);
-                core_graphics_context.set_should_smooth_fonts(false);
-                core_graphics_context.set_should_antialias(false);
-            }
-            RasterizationOptions::GrayscaleAa | RasterizationOptions::SubpixelAa => {
-                // FIXME(pcwalton): These shouldn't be handled the same!
-                core_graphics_context.set_allows_font_smoothing(true);
-                core_graphics_context.set_should_smooth_fonts(true);
-                core_graphics_context.set_should_antialias(true);
-            }
+        // `Deterministic` was handled above, so this is either bilevel or antialiased.
+        if rasterization_options == RasterizationOptions::Bilevel {
+            core_graphics_context.set_allows_font_smoothing(false);
+            core_graphics_context.set_should_smooth_fonts(false);
+            core_graphics_context.set_should_antialias(false);
+        } else {
+            // FIXME(pcwalton): Grayscale and subpixel AA shouldn't be handled the same!
+            core_graphics_context.set_allows_font_smoothing(true);
+            core_graphics_context.set_should_smooth_fonts(true);
+            core_graphics_context.set_should_antialias(true);
         }
 
         match canvas.format {
@@ -565,7 +621,76 @@ impl Font {
         let origin = CGPoint::new(0.0, 0.0);
         core_graphics_context.show_glyphs_at_positions(&[glyph_id as CGGlyph], &[origin]);
 
-        Ok(())
+        Ok(info)
+    }
+
+    /// Returns the pixel boundaries that the glyph will take up when rendered, like
+    /// `raster_bounds()`, along with the representation of the glyph that Core Text draws.
+    #[inline]
+    pub fn raster_bounds_with_info(
+        &self,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<(RectI, GlyphRasterInfo), GlyphLoadingError> {
+        let bounds = self.raster_bounds(
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )?;
+        let info = self.raster_info(glyph_id, point_size, hinting_options, rasterization_options);
+        Ok((bounds, info))
+    }
+
+    // Core Text only draws color glyphs in fonts that it marks as having them. Of those glyphs, it
+    // draws the `sbix` image of a glyph that has no outline, and composites the `COLR` layers of
+    // one that does. It ignores `CBDT` and `EBDT` bitmaps.
+    fn raster_info(
+        &self,
+        glyph_id: u32,
+        point_size: f32,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> GlyphRasterInfo {
+        let outline_info =
+            loader::outline_raster_info(self, hinting_options, rasterization_options);
+        if rasterization_options == RasterizationOptions::Deterministic
+            || self.core_text_font.symbolic_traits() & kCTFontColorGlyphsTrait == 0
+        {
+            return outline_info;
+        }
+
+        let has_outline = self
+            .core_text_font
+            .create_path_for_glyph(glyph_id as CGGlyph, &CG_AFFINE_TRANSFORM_IDENTITY)
+            .is_ok();
+        let representation = if has_outline {
+            if self.color_layers(glyph_id).is_empty() {
+                return outline_info;
+            }
+            GlyphRepresentation::ColorLayers
+        } else {
+            let strike_ppem = u16::try_from(glyph_id).ok().and_then(|glyph_id| {
+                let table = self.load_font_table(tables::TABLE_TAG_SBIX)?;
+                sbix::select_strike(&sbix::strikes(&table, glyph_id)?, point_size)
+            });
+            match strike_ppem {
+                Some(strike_ppem) => GlyphRepresentation::Bitmap {
+                    strike_ppem,
+                    scale: point_size / strike_ppem as f32,
+                    color: true,
+                },
+                None => return outline_info,
+            }
+        };
+        GlyphRasterInfo {
+            representation,
+            synthesis: outline_info.synthesis,
+        }
     }
 
     /// Returns true if and only if the font loader can perform hinting in the requested way.
@@ -632,6 +757,29 @@ impl Loader for Font {
         Font::from_native_font(native_font)
     }
 
+    fn from_native_handle(handle: &NativeHandle) -> Result<Self, FontLoadingError> {
+        // Core Text substitutes a fallback font for a descriptor that matches no installed font, so
+        // check that the font it resolved is the one that the descriptor names.
+        let descriptor = handle.core_text_font_descriptor();
+        let core_text_font = core_text::font::new_from_descriptor(descriptor, 16.0);
+        let requested_name = descriptor
+            .attributes()
+            .find(&CFString::new("NSFontNameAttribute"))
+            .and_then(|name| name.downcast::<CFString>())
+            .map(|name| name.to_string());
+        if let Some(requested_name) = requested_name {
+            if requested_name != core_text_font.postscript_name() {
+                return Err(FontLoadingError::UnsupportedHandle);
+            }
+        }
+        if let Some(requested_path) = descriptor.font_path() {
+            if core_text_font.copy_descriptor().font_path() != Some(requested_path) {
+                return Err(FontLoadingError::UnsupportedHandle);
+            }
+        }
+        unsafe { Ok(Font::from_core_text_font(core_text_font)) }
+    }
+
     #[inline]
     fn analyze_bytes(font_data: Arc<Vec<u8>>) -> Result<FileType, FontLoadingError> {
         Font::analyze_bytes(font_data)
@@ -720,6 +868,11 @@ impl Loader for Font {
         self.metrics()
     }
 
+    #[inline]
+    fn handle(&self) -> Option<Handle> {
+        self.handle()
+    }
+
     #[inline]
     fn copy_font_data(&self) -> Option<Arc<Vec<u8>>> {
         self.copy_font_data()
@@ -754,6 +907,44 @@ impl Loader for Font {
         )
     }
 
+    #[inline]
+    fn rasterize_glyph_with_info(
+        &self,
+        canvas: &mut Canvas,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<GlyphRasterInfo, GlyphLoadingError> {
+        self.rasterize_glyph_with_info(
+            canvas,
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+    }
+
+    #[inline]
+    fn raster_bounds_with_info(
+        &self,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
+    ) -> Result<(RectI, GlyphRasterInfo), GlyphLoadingError> {
+        self.raster_bounds_with_info(
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
+    }
+
     #[inline]
     fn get_fallbacks(&self, text: &str, locale: &str) -> FallbackResult<Self> {
         self.get_fallbacks(text, locale)
@@ -798,6 +989,28 @@ impl CGPointExt for CGPoint {
     }
 }
 
+// Creates a Core Text font from data in memory, without registering it with the font manager.
+//
+// Core Text copies the data into a `CFData`, which is owned by the returned font.
+fn new_core_text_font_from_data(font_data: &[u8]) -> Result<CTFont, FontLoadingError> {
+    let descriptor = new_core_text_font_descriptor_from_data(&CFData::from_buffer(font_data))?;
+    Ok(core_text::font::new_from_descriptor(&descriptor, 16.0))
+}
+
+// The descriptor retains the data, and releases it when it's dropped. Nothing is registered with
+// the font manager.
+fn new_core_text_font_descriptor_from_data(
+    cf_data: &CFData,
+) -> Result<CTFontDescriptor, FontLoadingError> {
+    unsafe {
+        let descriptor = CTFontManagerCreateFontDescriptorFromData(cf_data.as_concrete_TypeRef());
+        if descriptor.is_null() {
+            return Err(FontLoadingError::Parse);
+        }
+        Ok(CTFontDescriptor::wrap_under_create_rule(descriptor))
+    }
+}
+
 fn core_text_to_css_font_weight(core_text_weight: f32) -> Weight {
     let index = piecewise_linear_find_index(core_text_weight, &FONT_WEIGHT_MAPPING);
 
@@ -924,7 +1137,15 @@ fn unpack_data_fork_font(data: &mut [u8]) -> Result<(), FontLoadingError> {
 
 #[cfg(test)]
 mod test {
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::data::CFData;
+    use core_foundation::dictionary::CFDictionary;
+    use core_foundation::string::CFString;
+    use std::sync::Arc;
+
     use super::Font;
+    use crate::error::FontLoadingError;
+    use crate::handle::{Handle, NativeHandle};
     use crate::properties::{Stretch, Weight};
 
     #[cfg(feature = "source")]
@@ -946,6 +1167,67 @@ mod test {
         assert_eq!(font1.postscript_name().unwrap(), TEST_FONT_POSTSCRIPT_NAME);
     }
 
+    #[cfg(feature = "source")]
+    #[test]
+    fn test_from_native_handle() {
+        let font0 = SystemSource::new()
+            .select_by_postscript_name(TEST_FONT_POSTSCRIPT_NAME)
+            .unwrap()
+            .load()
+            .unwrap();
+        let descriptor = font0.native_font().copy_descriptor();
+        let handle = Handle::from_native(NativeHandle::from_core_text_font_descriptor(descriptor));
+        let font1 = Font::from_handle(&handle).unwrap();
+        assert_eq!(font1.postscript_name().unwrap(), TEST_FONT_POSTSCRIPT_NAME);
+    }
+
+    #[test]
+    fn test_from_native_handle_of_missing_font() {
+        // Core Text resolves this descriptor to a fallback font.
+        let attributes: CFDictionary<CFString, CFType> = CFDictionary::from_CFType_pairs(&[(
+            CFString::new("NSFontNameAttribute"),
+            CFString::new("FontKitMissingFont-Regular").as_CFType(),
+        )]);
+        let descriptor = core_text::font_descriptor::new_from_attributes(&attributes);
+        let handle = Handle::from_native(NativeHandle::from_core_text_font_descriptor(descriptor));
+        assert!(matches!(
+            Font::from_handle(&handle),
+            Err(FontLoadingError::UnsupportedHandle)
+        ));
+    }
+
+    #[test]
+    fn test_from_bytes_many_times() {
+        let font_data = Arc::new(
+            std::fs::read("resources/tests/eb-garamond/EBGaramond12-Regular.otf").unwrap(),
+        );
+        let family_count = core_text::font_collection::get_family_names().len();
+        for _ in 0..5000 {
+            let font = Font::from_bytes(font_data.clone(), 0).unwrap();
+            assert_eq!(font.postscript_name().unwrap(), "EBGaramond12-Regular");
+        }
+        // Memory fonts must not be registered with the font manager.
+        assert_eq!(
+            core_text::font_collection::get_family_names().len(),
+            family_count
+        );
+    }
+
+    #[test]
+    fn test_font_descriptor_releases_data() {
+        let font_data =
+            std::fs::read("resources/tests/eb-garamond/EBGaramond12-Regular.otf").unwrap();
+        let cf_data = CFData::from_buffer(&font_data);
+        let retain_count = cf_data.retain_count();
+        {
+            let descriptor = super::new_core_text_font_descriptor_from_data(&cf_data).unwrap();
+            let core_text_font = core_text::font::new_from_descriptor(&descriptor, 16.0);
+            assert_eq!(core_text_font.postscript_name(), "EBGaramond12-Regular");
+        }
+        // Once the descriptor is gone, nothing else holds on to the data.
+        assert_eq!(cf_data.retain_count(), retain_count);
+    }
+
     #[test]
     fn test_core_text_to_css_font_weight() {
         // Exact matches
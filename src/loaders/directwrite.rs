@@ -26,6 +26,7 @@ use dwrote::OutlineBuilder as DWriteOutlineBuilder;
 use dwrote::{DWRITE_TEXTURE_ALIASED_1x1, DWRITE_TEXTURE_CLEARTYPE_3x1};
 use dwrote::{DWRITE_GLYPH_RUN, DWRITE_MEASURING_MODE_NATURAL};
 use dwrote::{DWRITE_RENDERING_MODE_ALIASED, DWRITE_RENDERING_MODE_NATURAL};
+use lazy_static::lazy_static;
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::{RectF, RectI};
 use pathfinder_geometry::transform2d::Transform2F;
@@ -38,22 +39,36 @@ use std::io::{self, Read, Seek, SeekFrom};
 use std::os::windows::ffi::OsStringExt;
 use std::os::windows::io::AsRawHandle;
 use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{self, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use winapi::shared::minwindef::{FALSE, MAX_PATH};
+use winapi::ctypes::c_void;
+use winapi::shared::guiddef::{IsEqualGUID, REFIID};
+use winapi::shared::minwindef::{FALSE, MAX_PATH, ULONG};
+use winapi::shared::winerror::{E_NOINTERFACE, S_OK};
 use winapi::um::dwrite::DWRITE_NUMBER_SUBSTITUTION_METHOD_NONE;
 use winapi::um::dwrite::DWRITE_READING_DIRECTION;
 use winapi::um::dwrite::DWRITE_READING_DIRECTION_LEFT_TO_RIGHT;
+use winapi::um::dwrite::DWRITE_TEXTURE_TYPE;
+use winapi::um::dwrite::{IDWriteFactory, IDWriteFontFileLoader};
+use winapi::um::dwrite::{DWRITE_FONT_SIMULATIONS_BOLD, DWRITE_FONT_SIMULATIONS_OBLIQUE};
+use winapi::um::dwrite_3::{IDWriteFactory5, IDWriteInMemoryFontFileLoader};
 use winapi::um::fileapi;
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::um::winnt::HRESULT;
+use winapi::Interface;
+use wio::com::ComPtr;
 
-use crate::canvas::{Canvas, Format, RasterizationOptions};
+use crate::canvas::{Canvas, Format, RasterizationOptions, Synthesis};
 use crate::error::{FontLoadingError, GlyphLoadingError};
 use crate::file_type::FileType;
-use crate::handle::Handle;
+use crate::handle::{Handle, NativeHandle};
 use crate::hinting::HintingOptions;
 use crate::loader::{FallbackFont, FallbackResult, Loader};
 use crate::metrics::Metrics;
 use crate::outline::{OutlineBuilder, OutlineSink};
 use crate::properties::{Properties, Stretch, Style, Weight};
+use crate::rasterizer;
 
 const ERROR_BOUND: f32 = 0.0001;
 
@@ -75,6 +90,130 @@ pub struct Font {
     cached_data: Mutex<Option<Arc<Vec<u8>>>>,
 }
 
+lazy_static! {
+    static ref IN_MEMORY_FONT_FILE_LOADER: Option<InMemoryFontFileLoader> =
+        InMemoryFontFileLoader::new();
+}
+
+// DirectWrite's built-in loader for font data in memory, available through `IDWriteFactory5`.
+//
+// The loader is created and registered with the shared factory the first time it's needed and
+// stays registered for the lifetime of the process, so there's no per-font registration to leak.
+struct InMemoryFontFileLoader {
+    factory: ComPtr<IDWriteFactory5>,
+    loader: ComPtr<IDWriteInMemoryFontFileLoader>,
+}
+
+// The shared DirectWrite factory and the objects it creates are free-threaded.
+unsafe impl Send for InMemoryFontFileLoader {}
+unsafe impl Sync for InMemoryFontFileLoader {}
+
+impl InMemoryFontFileLoader {
+    fn new() -> Option<InMemoryFontFileLoader> {
+        unsafe {
+            let mut factory: *mut IDWriteFactory5 = ptr::null_mut();
+            let result = (*dwrote::DWriteFactory()).QueryInterface(
+                &IDWriteFactory5::uuidof(),
+                &mut factory as *mut *mut IDWriteFactory5 as *mut *mut c_void,
+            );
+            if result != S_OK || factory.is_null() {
+                return None;
+            }
+            let factory = ComPtr::from_raw(factory);
+
+            let mut loader = ptr::null_mut();
+            if factory.CreateInMemoryFontFileLoader(&mut loader) != S_OK || loader.is_null() {
+                return None;
+            }
+            let loader = ComPtr::from_raw(loader);
+
+            let result =
+                factory.RegisterFontFileLoader(loader.as_raw() as *mut IDWriteFontFileLoader);
+            if result != S_OK {
+                return None;
+            }
+
+            Some(InMemoryFontFileLoader { factory, loader })
+        }
+    }
+
+    // DirectWrite references the data in place, keeping it alive through the owner object,
+    // rather than making its own copy.
+    fn create_font_file(&self, font_data: Arc<Vec<u8>>) -> Option<DWriteFontFile> {
+        unsafe {
+            let (data, data_len) = (font_data.as_ptr(), font_data.len());
+            let owner = FontDataOwner::new(font_data);
+            let mut font_file = ptr::null_mut();
+            let result = self.loader.CreateInMemoryFontFileReference(
+                self.factory.as_raw() as *mut IDWriteFactory,
+                data as *const c_void,
+                data_len as u32,
+                owner.as_raw(),
+                &mut font_file,
+            );
+            if result != S_OK || font_file.is_null() {
+                return None;
+            }
+            Some(DWriteFontFile::take(ComPtr::from_raw(font_file)))
+        }
+    }
+}
+
+// A COM object that keeps font data alive for as long as DirectWrite references it.
+#[repr(C)]
+struct FontDataOwner {
+    vtbl: *const IUnknownVtbl,
+    ref_count: AtomicUsize,
+    _font_data: Arc<Vec<u8>>,
+}
+
+static FONT_DATA_OWNER_VTBL: IUnknownVtbl = IUnknownVtbl {
+    QueryInterface: FontDataOwner::query_interface,
+    AddRef: FontDataOwner::add_ref,
+    Release: FontDataOwner::release,
+};
+
+impl FontDataOwner {
+    fn new(font_data: Arc<Vec<u8>>) -> ComPtr<IUnknown> {
+        let owner = Box::new(FontDataOwner {
+            vtbl: &FONT_DATA_OWNER_VTBL,
+            ref_count: AtomicUsize::new(1),
+            _font_data: font_data,
+        });
+        unsafe { ComPtr::from_raw(Box::into_raw(owner) as *mut IUnknown) }
+    }
+
+    unsafe extern "system" fn query_interface(
+        this: *mut IUnknown,
+        riid: REFIID,
+        object: *mut *mut c_void,
+    ) -> HRESULT {
+        if IsEqualGUID(&*riid, &IUnknown::uuidof()) {
+            FontDataOwner::add_ref(this);
+            *object = this as *mut c_void;
+            S_OK
+        } else {
+            *object = ptr::null_mut();
+            E_NOINTERFACE
+        }
+    }
+
+    unsafe extern "system" fn add_ref(this: *mut IUnknown) -> ULONG {
+        let owner = &*(this as *const FontDataOwner);
+        (owner.ref_count.fetch_add(1, Ordering::Relaxed) + 1) as ULONG
+    }
+
+    unsafe extern "system" fn release(this: *mut IUnknown) -> ULONG {
+        let owner = this as *mut FontDataOwner;
+        let ref_count = (*owner).ref_count.fetch_sub(1, Ordering::Release) - 1;
+        if ref_count == 0 {
+            atomic::fence(Ordering::Acquire);
+            drop(Box::from_raw(owner));
+        }
+        ref_count as ULONG
+    }
+}
+
 struct MyTextAnalysisSource {
     text_utf16_len: u32,
     locale: String,
@@ -121,9 +260,27 @@ impl Font {
     ///
     /// If the data represents a collection (`.ttc`/`.otc`/etc.), `font_index` specifies the index
     /// of the font to load from it. If the data represents a single font, pass 0 for `font_index`.
+    ///
+    /// On Windows 10 Creators Update and later, the data is handed to DirectWrite's own in-memory
+    /// font file loader, which doesn't touch the filesystem and references the data without
+    /// copying it until the last reference to the font is dropped. Older versions of Windows fall
+    /// back to a custom font file loader.
+    #[inline]
     pub fn from_bytes(font_data: Arc<Vec<u8>>, font_index: u32) -> Result<Font, FontLoadingError> {
-        let font_file =
-            DWriteFontFile::new_from_data(font_data.clone()).ok_or(FontLoadingError::Parse)?;
+        Font::from_bytes_with_loader(font_data, font_index, IN_MEMORY_FONT_FILE_LOADER.as_ref())
+    }
+
+    // Uses a custom font file loader if `in_memory_loader` is `None`.
+    fn from_bytes_with_loader(
+        font_data: Arc<Vec<u8>>,
+        font_index: u32,
+        in_memory_loader: Option<&InMemoryFontFileLoader>,
+    ) -> Result<Font, FontLoadingError> {
+        let font_file = match in_memory_loader {
+            Some(loader) => loader.create_font_file(font_data.clone()),
+            None => DWriteFontFile::new_from_data(font_data.clone()),
+        };
+        let font_file = font_file.ok_or(FontLoadingError::Parse)?;
         Font::from_dwrite_font_file(font_file, font_index, Some(font_data))
     }
 
@@ -277,6 +434,15 @@ impl Font {
         self.dwrite_font_face.get_glyph_count() as u32
     }
 
+    // DirectWrite doesn't report out-of-range glyphs, so they're rejected up front.
+    fn check_glyph_id(&self, glyph_id: u32) -> Result<u16, GlyphLoadingError> {
+        if glyph_id < self.glyph_count() {
+            Ok(glyph_id as u16)
+        } else {
+            Err(GlyphLoadingError::NoSuchGlyph)
+        }
+    }
+
     /// Sends the vector path for a glyph to a path builder.
     ///
     /// If `hinting_mode` is not None, this function performs grid-fitting as requested before
@@ -292,10 +458,11 @@ impl Font {
     where
         S: OutlineSink,
     {
+        let glyph_id = self.check_glyph_id(glyph_id)?;
         let outline_sink = OutlineCanonicalizer::new();
         self.dwrite_font_face.get_glyph_run_outline(
             self.metrics().units_per_em as f32,
-            &[glyph_id as u16],
+            &[glyph_id],
             None,
             None,
             false,
@@ -314,9 +481,10 @@ impl Font {
 
     /// Returns the boundaries of a glyph in font units.
     pub fn typographic_bounds(&self, glyph_id: u32) -> Result<RectF, GlyphLoadingError> {
+        let glyph_id = self.check_glyph_id(glyph_id)?;
         let metrics = self
             .dwrite_font_face
-            .get_design_glyph_metrics(&[glyph_id as u16], false);
+            .get_design_glyph_metrics(&[glyph_id], false);
 
         let metrics = &metrics[0];
         let advance_width = metrics.advanceWidth as i32;
@@ -341,9 +509,10 @@ impl Font {
     /// Returns the distance from the origin of the glyph with the given ID to the next, in font
     /// units.
     pub fn advance(&self, glyph_id: u32) -> Result<Vector2F, GlyphLoadingError> {
+        let glyph_id = self.check_glyph_id(glyph_id)?;
         let metrics = self
             .dwrite_font_face
-            .get_design_glyph_metrics(&[glyph_id as u16], false);
+            .get_design_glyph_metrics(&[glyph_id], false);
         let metrics = &metrics[0];
         Ok(Vector2F::new(metrics.advanceWidth as f32, 0.0))
     }
@@ -428,6 +597,13 @@ impl Font {
         <Self as Loader>::handle(self)
     }
 
+    /// Returns the index of this font in the data returned by `copy_font_data()`, if that data
+    /// is a collection.
+    #[inline]
+    pub fn font_index(&self) -> u32 {
+        self.dwrite_font_face.get_index()
+    }
+
     /// Attempts to return the raw font data (contents of the font file).
     ///
     /// If this font is a member of a collection, this function returns the data for the entire
@@ -444,6 +620,15 @@ impl Font {
         (*font_data).clone()
     }
 
+    /// Returns the styles that DirectWrite simulates for this font.
+    pub fn synthesis(&self) -> Synthesis {
+        let simulations = unsafe { (*self.dwrite_font_face.as_ptr()).GetSimulations() };
+        Synthesis {
+            bold: (simulations & DWRITE_FONT_SIMULATIONS_BOLD) != 0,
+            oblique: (simulations & DWRITE_FONT_SIMULATIONS_OBLIQUE) != 0,
+        }
+    }
+
     /// Returns the pixel boundaries that the glyph will take up when rendered using this loader's
     /// rasterizer at the given size and origin.
     #[inline]
@@ -455,6 +640,10 @@ impl Font {
         hinting_options: HintingOptions,
         rasterization_options: RasterizationOptions,
     ) -> Result<RectI, GlyphLoadingError> {
+        if rasterization_options == RasterizationOptions::Deterministic {
+            return rasterizer::raster_bounds(self, glyph_id, point_size, transform);
+        }
+
         let dwrite_analysis = self.build_glyph_analysis(
             glyph_id,
             point_size,
@@ -463,12 +652,7 @@ impl Font {
             rasterization_options,
         )?;
 
-        let texture_type = match rasterization_options {
-            RasterizationOptions::Bilevel => DWRITE_TEXTURE_ALIASED_1x1,
-            RasterizationOptions::GrayscaleAa | RasterizationOptions::SubpixelAa => {
-                DWRITE_TEXTURE_CLEARTYPE_3x1
-            }
-        };
+        let texture_type = texture_type(rasterization_options);
 
         let texture_bounds = dwrite_analysis.get_alpha_texture_bounds(texture_type)?;
         let texture_width = texture_bounds.right - texture_bounds.left;
@@ -501,6 +685,10 @@ impl Font {
         // TODO(pcwalton): This is woefully incomplete. See WebRender's code for a more complete
         // implementation.
 
+        if rasterization_options == RasterizationOptions::Deterministic {
+            return rasterizer::rasterize_glyph(self, canvas, glyph_id, point_size, transform);
+        }
+
         let dwrite_analysis = self.build_glyph_analysis(
             glyph_id,
             point_size,
@@ -509,12 +697,7 @@ impl Font {
             rasterization_options,
         )?;
 
-        let texture_type = match rasterization_options {
-            RasterizationOptions::Bilevel => DWRITE_TEXTURE_ALIASED_1x1,
-            RasterizationOptions::GrayscaleAa | RasterizationOptions::SubpixelAa => {
-                DWRITE_TEXTURE_CLEARTYPE_3x1
-            }
-        };
+        let texture_type = texture_type(rasterization_options);
 
         // TODO(pcwalton): Avoid a copy in some cases by writing directly to the canvas.
         let texture_bounds = dwrite_analysis.get_alpha_texture_bounds(texture_type)?;
@@ -579,8 +762,8 @@ impl Font {
         _hinting_options: HintingOptions,
         rasterization_options: RasterizationOptions,
     ) -> Result<DWriteGlyphRunAnalysis, GlyphLoadingError> {
+        let glyph_id = self.check_glyph_id(glyph_id)?;
         unsafe {
-            let glyph_id = glyph_id as u16;
             let advance = 0.0;
             let offset = DWriteGlyphOffset {
                 advanceOffset: 0.0,
@@ -597,11 +780,11 @@ impl Font {
                 bidiLevel: 0,
             };
 
-            let rendering_mode = match rasterization_options {
-                RasterizationOptions::Bilevel => DWRITE_RENDERING_MODE_ALIASED,
-                RasterizationOptions::GrayscaleAa | RasterizationOptions::SubpixelAa => {
-                    DWRITE_RENDERING_MODE_NATURAL
-                }
+            // Callers handle `Deterministic` themselves, so this is either bilevel or antialiased.
+            let rendering_mode = if rasterization_options == RasterizationOptions::Bilevel {
+                DWRITE_RENDERING_MODE_ALIASED
+            } else {
+                DWRITE_RENDERING_MODE_NATURAL
             };
 
             Ok(DWriteGlyphRunAnalysis::create(
@@ -692,6 +875,15 @@ impl Font {
     }
 }
 
+// Callers handle `Deterministic` themselves, so this is either bilevel or antialiased.
+fn texture_type(rasterization_options: RasterizationOptions) -> DWRITE_TEXTURE_TYPE {
+    if rasterization_options == RasterizationOptions::Bilevel {
+        DWRITE_TEXTURE_ALIASED_1x1
+    } else {
+        DWRITE_TEXTURE_CLEARTYPE_3x1
+    }
+}
+
 // There might well be a more efficient impl that doesn't fully decode the text,
 // just looks at the utf-8 bytes.
 fn convert_len_utf16_to_utf8(text: &str, len_utf16: usize) -> usize {
@@ -751,6 +943,29 @@ impl Loader for Font {
         Font::from_native_font(native_font)
     }
 
+    fn from_native_handle(handle: &NativeHandle) -> Result<Self, FontLoadingError> {
+        let dwrite_font_face = handle
+            .dwrite_font_face()
+            .ok_or(FontLoadingError::NoSuchFontInCollection)?;
+        // Fonts outside the system collection are found in a collection of just their file.
+        let dwrite_font = DWriteFontCollection::system()
+            .get_font_from_face(&dwrite_font_face)
+            .or_else(|| {
+                let collection_loader =
+                    CustomFontCollectionLoaderImpl::new(&[handle.dwrite_font_file().clone()]);
+                DWriteFontCollection::from_loader(collection_loader)
+                    .get_font_from_face(&dwrite_font_face)
+            })
+            .ok_or(FontLoadingError::NoSuchFontInCollection)?;
+        // Keep the face we were given rather than creating one from `dwrite_font`, so that its
+        // simulations are preserved.
+        Ok(Font {
+            dwrite_font,
+            dwrite_font_face,
+            cached_data: Mutex::new(None),
+        })
+    }
+
     #[inline]
     fn analyze_bytes(font_data: Arc<Vec<u8>>) -> Result<FileType, FontLoadingError> {
         Font::analyze_bytes(font_data)
@@ -843,11 +1058,25 @@ impl Loader for Font {
         self.supports_hinting_options(hinting_options, for_rasterization)
     }
 
+    #[inline]
+    fn font_index(&self) -> u32 {
+        self.font_index()
+    }
+
     #[inline]
     fn copy_font_data(&self) -> Option<Arc<Vec<u8>>> {
         self.copy_font_data()
     }
 
+    #[inline]
+    fn synthesis(&self) -> Synthesis {
+        self.synthesis()
+    }
+
+    // DirectWrite's alpha textures only hold coverage, so color layers and color bitmaps are never
+    // drawn; glyphs are drawn from their outlines, with the simulations that `synthesis()`
+    // reports. The default `rasterize_glyph_with_info()` and `raster_bounds_with_info()` describe
+    // exactly that.
     #[inline]
     fn rasterize_glyph(
         &self,
@@ -960,3 +1189,38 @@ fn style_for_dwrite_style(style: DWriteFontStyle) -> Style {
         DWriteFontStyle::Italic => Style::Italic,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::sync::Arc;
+
+    use super::{Font, IN_MEMORY_FONT_FILE_LOADER};
+
+    static TEST_FONT_FILE_PATH: &str = "resources/tests/eb-garamond/EBGaramond12-Regular.otf";
+    static TEST_FONT_POSTSCRIPT_NAME: &str = "EBGaramond12-Regular";
+
+    #[test]
+    fn test_from_bytes_with_in_memory_loader() {
+        let loader = match IN_MEMORY_FONT_FILE_LOADER.as_ref() {
+            Some(loader) => loader,
+            // Windows versions before 10 Creators Update don't have the loader.
+            None => return,
+        };
+        let font_data = Arc::new(fs::read(TEST_FONT_FILE_PATH).unwrap());
+        let font = Font::from_bytes_with_loader(font_data.clone(), 0, Some(loader)).unwrap();
+        assert_eq!(font.postscript_name().unwrap(), TEST_FONT_POSTSCRIPT_NAME);
+
+        // DirectWrite and the font share the caller's data rather than copying it.
+        assert!(Arc::strong_count(&font_data) > 2);
+        assert!(Arc::ptr_eq(&font.copy_font_data().unwrap(), &font_data));
+    }
+
+    #[test]
+    fn test_from_bytes_with_custom_loader() {
+        let font_data = Arc::new(fs::read(TEST_FONT_FILE_PATH).unwrap());
+        let font = Font::from_bytes_with_loader(font_data.clone(), 0, None).unwrap();
+        assert_eq!(font.postscript_name().unwrap(), TEST_FONT_POSTSCRIPT_NAME);
+        assert!(Arc::ptr_eq(&font.copy_font_data().unwrap(), &font_data));
+    }
+}
@@ -53,6 +53,7 @@ use crate::loader::{FallbackResult, Loader};
 use crate::metrics::Metrics;
 use crate::outline::OutlineSink;
 use crate::properties::{Properties, Stretch, Style, Weight};
+use crate::rasterizer;
 use crate::utils;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -810,6 +811,10 @@ impl Font {
     ) -> Result<(), GlyphLoadingError> {
         // TODO(pcwalton): This is woefully incomplete. See WebRender's code for a more complete
         // implementation.
+        if rasterization_options == RasterizationOptions::Deterministic {
+            return rasterizer::rasterize_glyph(self, canvas, glyph_id, point_size, transform);
+        }
+
         unsafe {
             let matrix = transform.matrix.0 * F32x4::new(65536.0, -65536.0, -65536.0, 65536.0);
             let matrix = matrix.to_i32x4();
@@ -11,35 +11,85 @@
 //! A loader that uses swash API to load and rasterize fonts.
 
 use log::warn;
+use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::{RectF, RectI};
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::Vector2F;
+use std::cell::RefCell;
 use std::f32;
 use std::fmt::{self, Debug, Formatter};
 use std::fs::File;
 use std::io::{Seek, SeekFrom};
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use swash::scale::ScaleContext;
+use swash::zeno::{Command, PathData, Vector};
 
 use crate::canvas::{Canvas, RasterizationOptions};
+use crate::checksum::{self, ChecksumReport};
 use crate::error::{FontLoadingError, GlyphLoadingError};
 use crate::file_type::FileType;
 use crate::handle::Handle;
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_family = "windows",
+    target_arch = "wasm32"
+)))]
+use crate::handle::NativeHandle;
 use crate::hinting::HintingOptions;
 use crate::loader::{FallbackResult, Loader};
 use crate::metrics::Metrics;
 use crate::outline::OutlineSink;
 use crate::properties::{Properties, Stretch, Style, Weight};
+use crate::rasterizer;
+use crate::tables;
 use crate::utils;
 
+thread_local! {
+    // The scaler caches, which are reused by every outline request on this thread.
+    static SCALE_CONTEXT: RefCell<ScaleContext> = RefCell::new(ScaleContext::new());
+}
+
 /// A loader that uses Apple's Core Text API to load and rasterize fonts.
 #[derive(Clone)]
 pub struct Font {
-    data: Arc<Vec<u8>>,
+    data: FontData,
     // Offset to the table directory
     offset: u32,
     // Cache key
     key: swash::CacheKey,
+    // The file the font was loaded from, if known
+    path: Option<PathBuf>,
+    font_index: u32,
+}
+
+// The contents of the font file.
+//
+// Files loaded with `Font::from_file_mapped()` are memory-mapped, so that only the parts of the
+// font that are actually used (e.g. the outlines of the glyphs that are drawn) are paged in. This
+// keeps the memory footprint of large fonts, such as CJK fonts tens of megabytes in size, small.
+#[derive(Clone)]
+enum FontData {
+    Memory(Arc<Vec<u8>>),
+    #[cfg(unix)]
+    Mapped {
+        mmap: Arc<mmap::Mmap>,
+        // A copy of the mapped data, made the first time `copy_font_data()` is called and shared
+        // by all clones of the font.
+        copy: Arc<Mutex<Option<Arc<Vec<u8>>>>>,
+    },
+}
+
+impl FontData {
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        match *self {
+            FontData::Memory(ref data) => data,
+            #[cfg(unix)]
+            FontData::Mapped { ref mmap, .. } => mmap.as_slice(),
+        }
+    }
 }
 
 /// Core Text's representation of a font.
@@ -48,14 +98,24 @@ pub type NativeFont = Font;
 impl Font {
     /// Loads a font from raw font data (the contents of a `.ttf`/`.otf`/etc. file).
     pub fn from_bytes(data: Arc<Vec<u8>>, index: u32) -> Result<Font, FontLoadingError> {
+        Font::from_data(FontData::Memory(data), index)
+    }
+
+    fn from_data(data: FontData, index: u32) -> Result<Font, FontLoadingError> {
         // Create a temporary font reference for the first font in the file.
         // This will do some basic validation, compute the necessary offset
         // and generate a fresh cache key for us.
-        if let Some(font) = swash::FontRef::from_index(&data, index as usize) {
+        if let Some(font) = swash::FontRef::from_index(data.as_slice(), index as usize) {
             let (offset, key) = (font.offset, font.key);
             // Return our struct with the original file data and copies of the
             // offset and key from the font reference
-            return Ok(Self { data, offset, key });
+            return Ok(Self {
+                data,
+                offset,
+                key,
+                path: None,
+                font_index: index,
+            });
         };
         return Err(FontLoadingError::Parse);
     }
@@ -70,13 +130,47 @@ impl Font {
         Font::from_bytes(font_data, font_index)
     }
 
+    /// Loads a font from a `.ttf`/`.otf`/etc. file by memory-mapping it, so that glyph data is
+    /// only paged in when it's used.
+    ///
+    /// If the file is a collection (`.ttc`/`.otc`/etc.), `font_index` specifies the index of the
+    /// font to load from it. If the file represents a single font, pass 0 for `font_index`.
+    ///
+    /// On platforms without memory mapping, or if the file can't be mapped, the file is read
+    /// instead, as with `from_file()`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated, replaced, or modified in any way while the font or any of
+    /// its clones are alive. Truncating the file makes accesses to the missing pages fault (e.g.
+    /// with `SIGBUS`), and modifying it changes font data that the loader assumes is immutable.
+    pub unsafe fn from_file_mapped(
+        file: &mut File,
+        font_index: u32,
+    ) -> Result<Font, FontLoadingError> {
+        #[cfg(unix)]
+        {
+            if let Ok(mmap) = mmap::Mmap::map(file) {
+                let data = FontData::Mapped {
+                    mmap: Arc::new(mmap),
+                    copy: Arc::new(Mutex::new(None)),
+                };
+                return Font::from_data(data, font_index);
+            }
+        }
+
+        Font::from_file(file, font_index)
+    }
+
     /// Loads a font from the path to a `.ttf`/`.otf`/etc. file.
     ///
     /// If the file is a collection (`.ttc`/`.otc`/etc.), `font_index` specifies the index of the
     /// font to load from it. If the file represents a single font, pass 0 for `font_index`.
-    #[inline]
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn from_path<P: AsRef<Path>>(path: P, font_index: u32) -> Result<Font, FontLoadingError> {
-        <Font as Loader>::from_path(path, font_index)
+        let mut font = Font::from_file(&mut File::open(path.as_ref())?, font_index)?;
+        font.path = Some(path.as_ref().to_owned());
+        Ok(font)
     }
 
     /// Creates a font from a native API handle.
@@ -98,7 +192,7 @@ impl Font {
         // while completely safe, will nullify the performance optimizations of
         // the caching mechanisms used in this crate.
         swash::FontRef {
-            data: &self.data,
+            data: self.data.as_slice(),
             offset: self.offset,
             key: self.key,
         }
@@ -178,7 +272,7 @@ impl Font {
     ///
     /// Glyph IDs range from 0 inclusive to this value exclusive.
     pub fn glyph_count(&self) -> u32 {
-        unimplemented!()
+        self.as_ref().metrics(&[]).glyph_count as u32
     }
 
     /// Returns the usual glyph ID for a Unicode character.
@@ -186,8 +280,11 @@ impl Font {
     /// Be careful with this function; typographically correct character-to-glyph mapping must be
     /// done using a *shaper* such as HarfBuzz. This function is only useful for best-effort simple
     /// use cases like "what does character X look like on its own".
-    pub fn glyph_for_char(&self, _c: char) -> Option<u32> {
-        unimplemented!()
+    pub fn glyph_for_char(&self, character: char) -> Option<u32> {
+        match self.as_ref().charmap().map(character) {
+            0 => None,
+            glyph_id => Some(glyph_id as u32),
+        }
     }
 
     /// Returns the glyph ID for the specified glyph name.
@@ -201,17 +298,44 @@ impl Font {
     /// If `hinting_mode` is not None, this function performs grid-fitting as requested before
     /// sending the hinding outlines to the builder.
     ///
-    /// TODO(pcwalton): What should we do for bitmap glyphs?
+    /// Hinting isn't supported yet, so the outlines are always unhinted. Glyphs that only have
+    /// bitmaps return `GlyphLoadingError::NoOutline`.
     pub fn outline<S>(
         &self,
-        _glyph_id: u32,
+        glyph_id: u32,
         _: HintingOptions,
-        _sink: &mut S,
+        sink: &mut S,
     ) -> Result<(), GlyphLoadingError>
     where
         S: OutlineSink,
     {
-        unimplemented!()
+        if glyph_id >= self.glyph_count() {
+            return Err(GlyphLoadingError::NoSuchGlyph);
+        }
+
+        // A size of zero produces outlines in font units.
+        let outline = SCALE_CONTEXT
+            .with(|context| {
+                let mut context = context.borrow_mut();
+                let mut scaler = context.builder(self.as_ref()).build();
+                scaler.scale_outline(glyph_id as u16)
+            })
+            .ok_or(GlyphLoadingError::NoOutline)?;
+        for command in outline.path().commands() {
+            match command {
+                Command::MoveTo(to) => sink.move_to(to_vector(to)),
+                Command::LineTo(to) => sink.line_to(to_vector(to)),
+                Command::QuadTo(ctrl, to) => {
+                    sink.quadratic_curve_to(to_vector(ctrl), to_vector(to))
+                }
+                Command::CurveTo(ctrl0, ctrl1, to) => sink.cubic_curve_to(
+                    LineSegment2F::new(to_vector(ctrl0), to_vector(ctrl1)),
+                    to_vector(to),
+                ),
+                Command::Close => sink.close(),
+            }
+        }
+        Ok(())
     }
 
     /// Returns the boundaries of a glyph in font units.
@@ -232,23 +356,74 @@ impl Font {
 
     /// Retrieves various metrics that apply to the entire font.
     pub fn metrics(&self) -> Metrics {
-        unimplemented!()
+        let metrics = self.as_ref().metrics(&[]);
+        // The bounding box of all glyphs is only recorded in the `head` table.
+        let head_table = self.as_ref().table(tables::TABLE_TAG_HEAD).unwrap_or(&[]);
+        let read_coordinate = |offset| tables::read_i16(head_table, offset).unwrap_or(0) as f32;
+        let (x_min, y_min) = (read_coordinate(36), read_coordinate(38));
+        let (x_max, y_max) = (read_coordinate(40), read_coordinate(42));
+        Metrics {
+            units_per_em: metrics.units_per_em as u32,
+            ascent: metrics.ascent,
+            descent: -metrics.descent,
+            line_gap: metrics.leading,
+            underline_position: metrics.underline_offset,
+            underline_thickness: metrics.stroke_size,
+            cap_height: metrics.cap_height,
+            x_height: metrics.x_height,
+            bounding_box: RectF::from_points(
+                Vector2F::new(x_min, y_min),
+                Vector2F::new(x_max, y_max),
+            ),
+        }
     }
 
     /// Returns a handle to this font, if possible.
     ///
-    /// This is useful if you want to open the font with a different loader.
-    #[inline]
+    /// This is useful if you want to open the font with a different loader. Fonts loaded from a
+    /// path return a handle to that path, which avoids copying the font data.
     pub fn handle(&self) -> Option<Handle> {
-        <Self as Loader>::handle(self)
+        match self.path {
+            Some(ref path) => Some(Handle::from_path(path.clone(), self.font_index)),
+            None => self
+                .copy_font_data()
+                .map(|font_data| Handle::from_memory(font_data, self.font_index)),
+        }
+    }
+
+    /// Returns the index of this font in the data returned by `copy_font_data()`, if that data
+    /// is a collection.
+    #[inline]
+    pub fn font_index(&self) -> u32 {
+        self.font_index
     }
 
     /// Attempts to return the raw font data (contents of the font file).
     ///
     /// If this font is a member of a collection, this function returns the data for the entire
     /// collection.
+    ///
+    /// The data of fonts that were memory-mapped from a file is copied the first time this is
+    /// called, and the copy is returned from then on.
     pub fn copy_font_data(&self) -> Option<Arc<Vec<u8>>> {
-        Some(self.data.clone())
+        match self.data {
+            FontData::Memory(ref data) => Some(data.clone()),
+            #[cfg(unix)]
+            FontData::Mapped { ref mmap, ref copy } => {
+                let mut copy = copy.lock().unwrap();
+                Some(
+                    copy.get_or_insert_with(|| Arc::new(mmap.as_slice().to_vec()))
+                        .clone(),
+                )
+            }
+        }
+    }
+
+    /// Verifies the table checksums and the `head` checksum adjustment of the font data.
+    ///
+    /// Unlike the default implementation, this reads memory-mapped data in place.
+    pub fn verify_checksums(&self) -> Option<ChecksumReport> {
+        checksum::verify_checksums(self.data.as_slice(), self.font_index)
     }
 
     /// Returns the pixel boundaries that the glyph will take up when rendered using this loader's
@@ -256,13 +431,20 @@ impl Font {
     #[inline]
     pub fn raster_bounds(
         &self,
-        _glyph_id: u32,
-        _point_size: f32,
-        _transform: Transform2F,
-        _hinting_options: HintingOptions,
-        _rasterization_options: RasterizationOptions,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
+        hinting_options: HintingOptions,
+        rasterization_options: RasterizationOptions,
     ) -> Result<RectI, GlyphLoadingError> {
-        unimplemented!()
+        <Self as Loader>::raster_bounds(
+            self,
+            glyph_id,
+            point_size,
+            transform,
+            hinting_options,
+            rasterization_options,
+        )
     }
 
     /// Rasterizes a glyph to a canvas with the given size and origin.
@@ -279,13 +461,17 @@ impl Font {
     /// implementation.
     pub fn rasterize_glyph(
         &self,
-        _canvas: &mut Canvas,
-        _glyph_id: u32,
-        _point_size: f32,
-        _transform: Transform2F,
+        canvas: &mut Canvas,
+        glyph_id: u32,
+        point_size: f32,
+        transform: Transform2F,
         _hinting_options: HintingOptions,
-        _rasterization_options: RasterizationOptions,
+        rasterization_options: RasterizationOptions,
     ) -> Result<(), GlyphLoadingError> {
+        if rasterization_options == RasterizationOptions::Deterministic {
+            return rasterizer::rasterize_glyph(self, canvas, glyph_id, point_size, transform);
+        }
+
         unimplemented!()
     }
 
@@ -296,8 +482,13 @@ impl Font {
     /// retrieval of hinted *outlines*. If `for_rasterization` is true, this function returns true
     /// if and only if the loader supports *rasterizing* hinted glyphs.
     #[inline]
-    pub fn supports_hinting_options(&self, _hinting_options: HintingOptions, _: bool) -> bool {
-        unimplemented!()
+    pub fn supports_hinting_options(&self, hinting_options: HintingOptions, _: bool) -> bool {
+        match hinting_options {
+            HintingOptions::None => true,
+            HintingOptions::Vertical(..)
+            | HintingOptions::VerticalSubpixel(..)
+            | HintingOptions::Full(..) => false,
+        }
     }
 
     /// Get font fallback results for the given text and locale.
@@ -318,8 +509,8 @@ impl Font {
     ///
     /// [OpenType specification]: https://docs.microsoft.com/en-us/typography/opentype/spec/
     #[inline]
-    pub fn load_font_table(&self, _table_tag: u32) -> Option<Box<[u8]>> {
-        unimplemented!()
+    pub fn load_font_table(&self, table_tag: u32) -> Option<Box<[u8]>> {
+        self.as_ref().table(table_tag).map(|data| data.into())
     }
 }
 
@@ -336,11 +527,31 @@ impl Loader for Font {
         Font::from_file(file, font_index)
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    fn from_path<P>(path: P, font_index: u32) -> Result<Font, FontLoadingError>
+    where
+        P: AsRef<Path>,
+    {
+        Font::from_path(path, font_index)
+    }
+
     #[inline]
     unsafe fn from_native_font(native_font: Self::NativeFont) -> Self {
         Font::from_native_font(native_font)
     }
 
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows",
+        target_arch = "wasm32"
+    )))]
+    #[inline]
+    fn from_native_handle(handle: &NativeHandle) -> Result<Self, FontLoadingError> {
+        Font::from_path(handle.path(), handle.font_index())
+    }
+
     #[inline]
     fn analyze_bytes(font_data: Arc<Vec<u8>>) -> Result<FileType, FontLoadingError> {
         Font::analyze_bytes(font_data)
@@ -429,11 +640,26 @@ impl Loader for Font {
         self.metrics()
     }
 
+    #[inline]
+    fn handle(&self) -> Option<Handle> {
+        self.handle()
+    }
+
+    #[inline]
+    fn font_index(&self) -> u32 {
+        self.font_index()
+    }
+
     #[inline]
     fn copy_font_data(&self) -> Option<Arc<Vec<u8>>> {
         self.copy_font_data()
     }
 
+    #[inline]
+    fn verify_checksums(&self) -> Option<ChecksumReport> {
+        self.verify_checksums()
+    }
+
     #[inline]
     fn supports_hinting_options(
         &self,
@@ -474,11 +700,181 @@ impl Loader for Font {
     }
 }
 
+#[inline]
+fn to_vector(point: Vector) -> Vector2F {
+    Vector2F::new(point.x, point.y)
+}
+
 impl Debug for Font {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
         self.full_name().fmt(fmt)
     }
 }
 
+// A minimal read-only memory mapping of a file.
+#[cfg(unix)]
+mod mmap {
+    use std::fs::File;
+    use std::io::{Error as IOError, ErrorKind};
+    use std::os::unix::io::AsRawFd;
+    use std::ptr;
+    use std::slice;
+
+    pub struct Mmap {
+        ptr: *mut libc::c_void,
+        len: usize,
+    }
+
+    // The mapping is read-only and private, so it can be shared between threads.
+    unsafe impl Send for Mmap {}
+    unsafe impl Sync for Mmap {}
+
+    impl Mmap {
+        // mmap
+        pub fn map(file: &File) -> Result<Mmap, IOError> {
+            let len = file.metadata()?.len();
+            // Empty mappings aren't allowed.
+            if len == 0 || len > usize::MAX as u64 {
+                return Err(IOError::new(ErrorKind::InvalidInput, "can't map file"));
+            }
+            let len = len as usize;
+
+            unsafe {
+                let ptr = libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ,
+                    libc::MAP_PRIVATE,
+                    file.as_raw_fd(),
+                    0,
+                );
+                if ptr == libc::MAP_FAILED {
+                    return Err(IOError::last_os_error());
+                }
+
+                // Glyphs are looked up all over the file, so reading ahead is wasted effort.
+                libc::madvise(ptr, len, libc::MADV_RANDOM);
+
+                Ok(Mmap { ptr, len })
+            }
+        }
+
+        #[inline]
+        pub fn as_slice(&self) -> &[u8] {
+            unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+
+    impl Drop for Mmap {
+        fn drop(&mut self) {
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::Font;
+    use crate::handle::Handle;
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows",
+        target_arch = "wasm32"
+    )))]
+    use crate::handle::NativeHandle;
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows",
+        target_arch = "wasm32"
+    )))]
+    use std::env;
+    use std::fs::{self, File};
+    use std::sync::Arc;
+
+    static TEST_FONT_FILE_PATH: &str = "resources/tests/eb-garamond/EBGaramond12-Regular.otf";
+    static TEST_FONT_COLLECTION_FILE_PATH: &str = "resources/tests/eb-garamond/EBGaramond12.otc";
+
+    #[test]
+    fn test_from_path() {
+        let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+        assert_eq!(
+            font.postscript_name().unwrap(),
+            "EBGaramond12-Regular".to_owned()
+        );
+        assert_eq!(
+            *font.copy_font_data().unwrap(),
+            fs::read(TEST_FONT_FILE_PATH).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_file_mapped() {
+        let mut file = File::open(TEST_FONT_COLLECTION_FILE_PATH).unwrap();
+        let font = unsafe { Font::from_file_mapped(&mut file, 1).unwrap() };
+        assert_eq!(
+            font.postscript_name().unwrap(),
+            "EBGaramond12-Italic".to_owned()
+        );
+        assert!(font.verify_checksums().unwrap().is_valid());
+
+        // The data is copied once and shared from then on.
+        let font_data = font.copy_font_data().unwrap();
+        assert_eq!(
+            *font_data,
+            fs::read(TEST_FONT_COLLECTION_FILE_PATH).unwrap()
+        );
+        assert!(Arc::ptr_eq(
+            &font_data,
+            &font.clone().copy_font_data().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_handle() {
+        let font = Font::from_path(TEST_FONT_COLLECTION_FILE_PATH, 1).unwrap();
+        match font.handle().unwrap() {
+            Handle::Path { path, font_index } => {
+                assert_eq!(path.to_str(), Some(TEST_FONT_COLLECTION_FILE_PATH));
+                assert_eq!(font_index, 1);
+            }
+            handle => panic!("unexpected handle: {:?}", handle),
+        }
+        let font = Font::from_handle(&font.handle().unwrap()).unwrap();
+        assert_eq!(
+            font.postscript_name().unwrap(),
+            "EBGaramond12-Italic".to_owned()
+        );
+    }
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows",
+        target_arch = "wasm32"
+    )))]
+    #[test]
+    fn test_from_native_handle() {
+        let path = env::current_dir()
+            .unwrap()
+            .join(TEST_FONT_COLLECTION_FILE_PATH);
+        let pattern = format!(
+            "EB Garamond 12:style=Italic:file={}:index=1",
+            path.display()
+        );
+        let handle = NativeHandle::from_fontconfig_pattern(&pattern).unwrap();
+        assert_eq!(handle.fontconfig_pattern(), pattern);
+        assert_eq!(handle.path(), path);
+        assert_eq!(handle.font_index(), 1);
+
+        let font = Font::from_handle(&Handle::from_native(handle)).unwrap();
+        assert_eq!(
+            font.postscript_name().unwrap(),
+            "EBGaramond12-Italic".to_owned()
+        );
+
+        assert!(NativeHandle::from_fontconfig_pattern("EB Garamond 12:style=Italic").is_none());
+    }
+}
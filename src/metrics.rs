@@ -13,6 +13,43 @@
 //! For OpenType fonts, these mostly come from the `OS/2` table.
 
 use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+
+/// The number of points per inch. Points are the unit of font sizes.
+pub const POINTS_PER_INCH: f32 = 72.0;
+
+/// A font size in points at a given resolution in dots per inch.
+///
+/// The rasterization functions of the loaders take a bare point size and treat points as pixels,
+/// which is only correct at 72 DPI. The `*_for_size()` methods of `Loader` take a `TextSize`
+/// instead, so HiDPI displays are handled in one place.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextSize {
+    /// The font size in points.
+    pub pt: f32,
+    /// The resolution of the device in dots per inch.
+    pub dpi: f32,
+}
+
+impl TextSize {
+    /// Creates a font size in points at the given resolution in dots per inch.
+    #[inline]
+    pub fn new(pt: f32, dpi: f32) -> TextSize {
+        TextSize { pt, dpi }
+    }
+
+    /// Creates a font size from a size in device pixels per em.
+    #[inline]
+    pub fn from_px_per_em(px_per_em: f32) -> TextSize {
+        TextSize::new(px_per_em, POINTS_PER_INCH)
+    }
+
+    /// Returns the font size in device pixels per em.
+    #[inline]
+    pub fn px_per_em(self) -> f32 {
+        self.pt * self.dpi / POINTS_PER_INCH
+    }
+}
 
 /// Various metrics that apply to the entire font.
 ///
@@ -56,3 +93,233 @@ pub struct Metrics {
     /// This corresponds to the `xMin`/`xMax`/`yMin`/`yMax` values in the OpenType `head` table.
     pub bounding_box: RectF,
 }
+
+/// The metrics of a font scaled to device pixels.
+///
+/// Vertical distances follow the same conventions as `Metrics`: positive values are above the
+/// baseline, so `descent` is typically negative.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScaledMetrics {
+    /// The font size in pixels per em.
+    pub px_per_em: f32,
+    /// The maximum amount the font rises above the baseline, in pixels.
+    pub ascent: f32,
+    /// The maximum amount the font descends below the baseline, in pixels.
+    pub descent: f32,
+    /// The extra space between the descent of one line and the ascent of the next, in pixels.
+    pub line_gap: f32,
+    /// The suggested distance of the top of the underline from the baseline, in pixels.
+    pub underline_position: f32,
+    /// A suggested value for the underline thickness, in pixels.
+    pub underline_thickness: f32,
+    /// The approximate amount that uppercase letters rise above the baseline, in pixels.
+    pub cap_height: f32,
+    /// The approximate amount that non-ascending lowercase letters rise above the baseline, in
+    /// pixels.
+    pub x_height: f32,
+    /// A rectangle that surrounds all bounding boxes of all glyphs, in pixels.
+    pub bounding_box: RectF,
+}
+
+/// How the height of a line of text is computed from the font metrics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineHeight {
+    /// The line is `ascent - descent + line_gap` tall, with the whole line gap below the descent.
+    ///
+    /// This is how most platform text APIs lay out lines.
+    Normal,
+    /// The line is `ascent - descent + line_gap` tall, with half of the line gap above the ascent
+    /// and half below the descent.
+    ///
+    /// This is how CSS lays out lines.
+    HalfLeading,
+    /// The line is `ascent - descent` tall; the line gap is ignored.
+    Tight,
+}
+
+impl Metrics {
+    /// Returns the size of one font unit in pixels, at the given point size and resolution in
+    /// dots per inch.
+    ///
+    /// All of the scaling helpers in `font-kit` convert from font units to pixels with this
+    /// factor.
+    #[inline]
+    pub fn pixels_per_font_unit(&self, point_size: f32, dpi: f32) -> f32 {
+        point_size * dpi / POINTS_PER_INCH / self.units_per_em as f32
+    }
+
+    /// Scales these metrics to the given point size.
+    ///
+    /// Like the rasterization functions of the loaders, this treats points as pixels, which is
+    /// correct at 72 DPI. To scale to a specific device resolution, use `to_device()`.
+    #[inline]
+    pub fn scale(&self, point_size: f32) -> ScaledMetrics {
+        self.to_device(point_size)
+    }
+
+    /// Scales these metrics to a font size given in device pixels per em.
+    ///
+    /// For a size in points at a given resolution, pass `point_size * dpi / 72.0`.
+    pub fn to_device(&self, px_per_em: f32) -> ScaledMetrics {
+        let scale = self.pixels_per_font_unit(px_per_em, POINTS_PER_INCH);
+        ScaledMetrics {
+            px_per_em,
+            ascent: self.ascent * scale,
+            descent: self.descent * scale,
+            line_gap: self.line_gap * scale,
+            underline_position: self.underline_position * scale,
+            underline_thickness: self.underline_thickness * scale,
+            cap_height: self.cap_height * scale,
+            x_height: self.x_height * scale,
+            bounding_box: self.bounding_box * scale,
+        }
+    }
+
+    /// Scales these metrics to the given font size.
+    #[inline]
+    pub fn scale_for_size(&self, size: TextSize) -> ScaledMetrics {
+        self.to_device(size.px_per_em())
+    }
+}
+
+impl ScaledMetrics {
+    /// Returns the height of a line of text, in pixels.
+    pub fn line_height(&self, policy: LineHeight) -> f32 {
+        match policy {
+            LineHeight::Normal | LineHeight::HalfLeading => {
+                self.ascent - self.descent + self.line_gap
+            }
+            LineHeight::Tight => self.ascent - self.descent,
+        }
+    }
+
+    /// Returns the distance from the top of a line of text to its baseline, in pixels.
+    pub fn baseline(&self, policy: LineHeight) -> f32 {
+        match policy {
+            LineHeight::Normal | LineHeight::Tight => self.ascent,
+            LineHeight::HalfLeading => self.ascent + self.line_gap * 0.5,
+        }
+    }
+}
+
+/// How values scaled from font units to pixels are rounded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoundingMode {
+    /// The value is left unrounded.
+    None,
+    /// The value is rounded to the nearest whole pixel, with halfway cases rounded away from zero.
+    Nearest,
+    /// The value is rounded down to a whole pixel.
+    Floor,
+    /// The value is rounded up to a whole pixel.
+    Ceil,
+}
+
+impl RoundingMode {
+    /// Rounds a value in pixels according to this mode.
+    #[inline]
+    pub fn round(self, value: f32) -> f32 {
+        match self {
+            RoundingMode::None => value,
+            RoundingMode::Nearest => value.round(),
+            RoundingMode::Floor => value.floor(),
+            RoundingMode::Ceil => value.ceil(),
+        }
+    }
+
+    /// Rounds each component of a vector in pixels according to this mode.
+    #[inline]
+    pub fn round_vector(self, vector: Vector2F) -> Vector2F {
+        Vector2F::new(self.round(vector.x()), self.round(vector.y()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pathfinder_geometry::rect::RectF;
+    use pathfinder_geometry::vector::Vector2F;
+
+    use super::{LineHeight, Metrics, RoundingMode, TextSize};
+
+    fn metrics(units_per_em: u32) -> Metrics {
+        Metrics {
+            units_per_em,
+            ascent: 0.0,
+            descent: 0.0,
+            line_gap: 0.0,
+            underline_position: 0.0,
+            underline_thickness: 0.0,
+            cap_height: 0.0,
+            x_height: 0.0,
+            bounding_box: RectF::default(),
+        }
+    }
+
+    #[test]
+    fn test_pixels_per_font_unit() {
+        assert_eq!(metrics(1000).pixels_per_font_unit(12.0, 72.0), 0.012);
+        assert_eq!(metrics(2048).pixels_per_font_unit(12.0, 96.0), 0.0078125);
+    }
+
+    #[test]
+    fn test_text_size() {
+        let size = TextSize::new(12.0, 144.0);
+        assert_eq!(size.px_per_em(), 24.0);
+        assert_eq!(TextSize::from_px_per_em(24.0).px_per_em(), 24.0);
+
+        let metrics = metrics(1000);
+        assert_eq!(
+            metrics.pixels_per_font_unit(size.pt, size.dpi),
+            metrics.pixels_per_font_unit(size.px_per_em(), 72.0)
+        );
+        assert_eq!(
+            metrics.scale_for_size(size),
+            metrics.to_device(size.px_per_em())
+        );
+    }
+
+    #[test]
+    fn test_scale() {
+        let mut metrics = metrics(1000);
+        metrics.ascent = 800.0;
+        metrics.descent = -200.0;
+        metrics.line_gap = 100.0;
+        metrics.bounding_box = RectF::new(Vector2F::new(-50.0, -250.0), Vector2F::splat(1000.0));
+
+        let scaled_metrics = metrics.scale(20.0);
+        assert_eq!(scaled_metrics, metrics.to_device(20.0));
+        assert_eq!(scaled_metrics.px_per_em, 20.0);
+        assert_eq!(scaled_metrics.ascent, 16.0);
+        assert_eq!(scaled_metrics.descent, -4.0);
+        assert_eq!(scaled_metrics.line_gap, 2.0);
+        assert_eq!(
+            scaled_metrics.bounding_box,
+            RectF::new(Vector2F::new(-1.0, -5.0), Vector2F::splat(20.0))
+        );
+
+        assert_eq!(scaled_metrics.line_height(LineHeight::Normal), 22.0);
+        assert_eq!(scaled_metrics.line_height(LineHeight::HalfLeading), 22.0);
+        assert_eq!(scaled_metrics.line_height(LineHeight::Tight), 20.0);
+        assert_eq!(scaled_metrics.baseline(LineHeight::Normal), 16.0);
+        assert_eq!(scaled_metrics.baseline(LineHeight::HalfLeading), 17.0);
+        assert_eq!(scaled_metrics.baseline(LineHeight::Tight), 16.0);
+    }
+
+    #[test]
+    fn test_rounding_mode() {
+        let vector = Vector2F::new(2.5, -2.5);
+        assert_eq!(RoundingMode::None.round_vector(vector), vector);
+        assert_eq!(
+            RoundingMode::Nearest.round_vector(vector),
+            Vector2F::new(3.0, -3.0)
+        );
+        assert_eq!(
+            RoundingMode::Floor.round_vector(vector),
+            Vector2F::new(2.0, -3.0)
+        );
+        assert_eq!(
+            RoundingMode::Ceil.round_vector(vector),
+            Vector2F::new(3.0, -2.0)
+        );
+    }
+}
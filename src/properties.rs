@@ -15,6 +15,8 @@
 
 use std::fmt::{self, Debug, Display, Formatter};
 
+use crate::loader::Loader;
+
 /// Properties that specify which font in a family to use: e.g. style, weight, and stretchiness.
 ///
 /// This object supports a method chaining style for idiomatic initialization; e.g.
@@ -163,3 +165,137 @@ impl Stretch {
         Stretch::ULTRA_EXPANDED.0,
     ];
 }
+
+bitflags! {
+    /// A set of OpenType layout features that a face can be required to support.
+    ///
+    /// These are the features that renderers commonly refuse to synthesize: if a document asks for
+    /// small caps and the selected face has no `smcp` feature, the result is either faux small caps
+    /// (scaled-down capitals) or nothing at all. Use `Requirements::features()` to have font
+    /// matching skip faces that lack them.
+    #[derive(Default)]
+    pub struct Features: u32 {
+        /// Small capitals from lowercase letters (`smcp`).
+        const SMALL_CAPS = 0x0001;
+        /// Small capitals from capital letters (`c2sc`).
+        const CAPS_TO_SMALL_CAPS = 0x0002;
+        /// Oldstyle (lowercase) figures (`onum`).
+        const OLDSTYLE_FIGURES = 0x0004;
+        /// Lining (uppercase) figures (`lnum`).
+        const LINING_FIGURES = 0x0008;
+        /// Tabular (fixed-width) figures (`tnum`).
+        const TABULAR_FIGURES = 0x0010;
+        /// Proportional figures (`pnum`).
+        const PROPORTIONAL_FIGURES = 0x0020;
+        /// Slashed zero (`zero`).
+        const SLASHED_ZERO = 0x0040;
+        /// Diagonal fractions (`frac`).
+        const FRACTIONS = 0x0080;
+        /// Superscript forms (`sups`).
+        const SUPERSCRIPT = 0x0100;
+        /// Subscript forms (`subs`).
+        const SUBSCRIPT = 0x0200;
+        /// Ordinal forms (`ordn`).
+        const ORDINALS = 0x0400;
+        /// Case-sensitive forms (`case`).
+        const CASE_SENSITIVE_FORMS = 0x0800;
+    }
+}
+
+impl Features {
+    // Mapping from each flag to its OpenType feature tag.
+    const TAGS: [(Features, u32); 12] = [
+        (Features::SMALL_CAPS, 0x736d6370),           // 'smcp'
+        (Features::CAPS_TO_SMALL_CAPS, 0x63327363),   // 'c2sc'
+        (Features::OLDSTYLE_FIGURES, 0x6f6e756d),     // 'onum'
+        (Features::LINING_FIGURES, 0x6c6e756d),       // 'lnum'
+        (Features::TABULAR_FIGURES, 0x746e756d),      // 'tnum'
+        (Features::PROPORTIONAL_FIGURES, 0x706e756d), // 'pnum'
+        (Features::SLASHED_ZERO, 0x7a65726f),         // 'zero'
+        (Features::FRACTIONS, 0x66726163),            // 'frac'
+        (Features::SUPERSCRIPT, 0x73757073),          // 'sups'
+        (Features::SUBSCRIPT, 0x73756273),            // 'subs'
+        (Features::ORDINALS, 0x6f72646e),             // 'ordn'
+        (Features::CASE_SENSITIVE_FORMS, 0x63617365), // 'case'
+    ];
+
+    /// Returns the set of known features among the given OpenType feature tags.
+    ///
+    /// Tags that don't correspond to any flag are ignored.
+    pub fn from_tags(tags: &[u32]) -> Features {
+        let mut features = Features::empty();
+        for &(feature, feature_tag) in &Features::TAGS {
+            if tags.contains(&feature_tag) {
+                features |= feature;
+            }
+        }
+        features
+    }
+
+    /// Returns the OpenType feature tags of the features in this set.
+    pub fn tags(&self) -> Vec<u32> {
+        Features::TAGS
+            .iter()
+            .filter(|&&(feature, _)| self.contains(feature))
+            .map(|&(_, feature_tag)| feature_tag)
+            .collect()
+    }
+}
+
+/// Criteria beyond the CSS properties that a face must satisfy in order to be selected by font
+/// matching.
+///
+/// Unlike `Properties`, which only rank the faces of a family, requirements are hard constraints:
+/// faces that don't satisfy them are never selected. Checking them requires loading each
+/// candidate face, so the default (empty) requirements are cheapest.
+///
+/// This object supports the same method chaining style as `Properties`; e.g.
+///
+///     # use font_kit::properties::{Features, Requirements};
+///     println!("{:?}", Requirements::new().features(Features::SMALL_CAPS));
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Requirements {
+    /// The OpenType layout features the face must support.
+    pub features: Features,
+    /// Whether the face's `.notdef` glyph must be visible, so that missing characters are drawn
+    /// as boxes rather than disappearing.
+    pub visible_notdef: bool,
+}
+
+impl Requirements {
+    /// Initializes an empty set of requirements, which every face satisfies.
+    #[inline]
+    pub fn new() -> Requirements {
+        Requirements::default()
+    }
+
+    /// Sets the required features and returns this requirement set for method chaining.
+    #[inline]
+    pub fn features(&mut self, features: Features) -> &mut Requirements {
+        self.features = features;
+        self
+    }
+
+    /// Sets whether the face's `.notdef` glyph must be visible and returns this requirement set
+    /// for method chaining.
+    #[inline]
+    pub fn visible_notdef(&mut self, visible_notdef: bool) -> &mut Requirements {
+        self.visible_notdef = visible_notdef;
+        self
+    }
+
+    /// Returns true if and only if every face satisfies these requirements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty() && !self.visible_notdef
+    }
+
+    /// Returns true if and only if the given font satisfies these requirements.
+    pub fn is_satisfied_by<F>(&self, font: &F) -> bool
+    where
+        F: Loader,
+    {
+        (self.features.is_empty() || font.supported_features().contains(self.features))
+            && (!self.visible_notdef || font.notdef_is_blank() == Ok(false))
+    }
+}
@@ -0,0 +1,421 @@
+// font-kit/src/rasterizer.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A pure-Rust rasterizer for glyph outlines, used for `RasterizationOptions::Deterministic`.
+//!
+//! Unlike the platform rasterizers, the output of this rasterizer depends only on the unhinted
+//! glyph outline, so it's the same on every platform. It's a signed-area accumulation rasterizer
+//! in the style of `font-rs`.
+
+use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::rect::{RectF, RectI};
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::{Vector2F, Vector2I};
+
+use crate::canvas::{Canvas, Format, RasterizationBudget};
+use crate::error::GlyphLoadingError;
+use crate::hinting::HintingOptions;
+use crate::loader::Loader;
+use crate::outline::OutlineSink;
+
+// The maximum distance, in pixels, between a curve and the line segments that approximate it.
+const FLATTENING_TOLERANCE: f32 = 0.1;
+// Bounds the work done for a single curve, no matter how large it is.
+const MAX_CURVE_SEGMENTS: u32 = 256;
+// The number of rows of line segments drawn between checks of the rasterization budget.
+const BUDGET_CHECK_INTERVAL: usize = 4096;
+
+/// Returns the pixel boundaries of the glyph outline at the given size and transform. The origin
+/// of the coordinate space is at the top left.
+pub(crate) fn raster_bounds<F>(
+    font: &F,
+    glyph_id: u32,
+    point_size: f32,
+    transform: Transform2F,
+) -> Result<RectI, GlyphLoadingError>
+where
+    F: Loader,
+{
+    let mut bounds_sink = BoundsSink {
+        transform: glyph_to_pixel_transform(font, point_size, transform),
+        bounds: None,
+    };
+    font.outline(glyph_id, HintingOptions::None, &mut bounds_sink)?;
+    match bounds_sink.bounds {
+        Some(bounds) => Ok(bounds.round_out().to_i32()),
+        None => Ok(RectI::default()),
+    }
+}
+
+/// Rasterizes the glyph outline to the canvas at the given size and transform.
+///
+/// The glyph is drawn in white; for RGB and RGBA canvases, every channel receives the coverage.
+/// Like the platform rasterizers, this overwrites the previous contents of the canvas. Glyphs
+/// without an outline return `GlyphLoadingError::NoOutline`.
+#[inline]
+pub(crate) fn rasterize_glyph<F>(
+    font: &F,
+    canvas: &mut Canvas,
+    glyph_id: u32,
+    point_size: f32,
+    transform: Transform2F,
+) -> Result<(), GlyphLoadingError>
+where
+    F: Loader,
+{
+    rasterize_glyph_with_budget(
+        font,
+        canvas,
+        glyph_id,
+        point_size,
+        transform,
+        &RasterizationBudget::new(),
+    )
+}
+
+/// Like `rasterize_glyph()`, but gives up as soon as the budget is exhausted. The canvas is left
+/// untouched in that case.
+pub(crate) fn rasterize_glyph_with_budget<F>(
+    font: &F,
+    canvas: &mut Canvas,
+    glyph_id: u32,
+    point_size: f32,
+    transform: Transform2F,
+    budget: &RasterizationBudget,
+) -> Result<(), GlyphLoadingError>
+where
+    F: Loader,
+{
+    budget.check()?;
+    if canvas.size.x() <= 0 || canvas.size.y() <= 0 {
+        return Ok(());
+    }
+
+    let transform = glyph_to_pixel_transform(font, point_size, transform);
+    let mut rasterizer = Rasterizer::new(canvas.size, transform, budget.clone());
+    font.outline(glyph_id, HintingOptions::None, &mut rasterizer)?;
+    rasterizer.close();
+    rasterizer.status?;
+    rasterizer.write_to(canvas);
+    Ok(())
+}
+
+// Maps font units, with the origin at the bottom left, to pixels, with the origin at the top
+// left. This matches the default `Loader::raster_bounds()` implementation.
+fn glyph_to_pixel_transform<F>(font: &F, point_size: f32, transform: Transform2F) -> Transform2F
+where
+    F: Loader,
+{
+    let scale = point_size / font.metrics().units_per_em as f32;
+    transform * Transform2F::from_scale(Vector2F::new(scale, -scale))
+}
+
+fn curve_segment_count(deviation: f32) -> u32 {
+    let segment_count = f32::ceil(f32::sqrt(deviation / FLATTENING_TOLERANCE));
+    if segment_count >= MAX_CURVE_SEGMENTS as f32 {
+        MAX_CURVE_SEGMENTS
+    } else if segment_count >= 1.0 {
+        segment_count as u32
+    } else {
+        1
+    }
+}
+
+struct BoundsSink {
+    transform: Transform2F,
+    bounds: Option<RectF>,
+}
+
+impl BoundsSink {
+    fn add_point(&mut self, point: Vector2F) {
+        let point = self.transform * point;
+        self.bounds = Some(match self.bounds {
+            None => RectF::from_points(point, point),
+            Some(bounds) => bounds.union_point(point),
+        });
+    }
+}
+
+// Control points are included, which is conservative: a Bézier curve is always contained within
+// the convex hull of its control points.
+impl OutlineSink for BoundsSink {
+    fn move_to(&mut self, to: Vector2F) {
+        self.add_point(to);
+    }
+
+    fn line_to(&mut self, to: Vector2F) {
+        self.add_point(to);
+    }
+
+    fn quadratic_curve_to(&mut self, ctrl: Vector2F, to: Vector2F) {
+        self.add_point(ctrl);
+        self.add_point(to);
+    }
+
+    fn cubic_curve_to(&mut self, ctrl: LineSegment2F, to: Vector2F) {
+        self.add_point(ctrl.from());
+        self.add_point(ctrl.to());
+        self.add_point(to);
+    }
+
+    fn close(&mut self) {}
+}
+
+struct Rasterizer {
+    transform: Transform2F,
+    width: usize,
+    height: usize,
+    // Each row has two extra cells so that edges clamped to the right side of the canvas have
+    // somewhere to go.
+    stride: usize,
+    accumulation: Vec<f32>,
+    first_position: Vector2F,
+    current_position: Vector2F,
+    budget: RasterizationBudget,
+    // Outline sinks can't fail, so once the budget is exhausted, the error is recorded here and
+    // the rest of the outline is ignored.
+    status: Result<(), GlyphLoadingError>,
+    rows_since_budget_check: usize,
+}
+
+impl Rasterizer {
+    fn new(size: Vector2I, transform: Transform2F, budget: RasterizationBudget) -> Rasterizer {
+        let (width, height) = (size.x() as usize, size.y() as usize);
+        let stride = width + 2;
+        Rasterizer {
+            transform,
+            width,
+            height,
+            stride,
+            accumulation: vec![0.0; stride * height],
+            first_position: Vector2F::zero(),
+            current_position: Vector2F::zero(),
+            budget,
+            status: Ok(()),
+            rows_since_budget_check: 0,
+        }
+    }
+
+    fn draw_line(&mut self, from: Vector2F, to: Vector2F) {
+        if from.y() == to.y() || self.status.is_err() {
+            return;
+        }
+        let (direction, from, to) = if from.y() < to.y() {
+            (1.0, from, to)
+        } else {
+            (-1.0, to, from)
+        };
+
+        let y_start = from.y().max(0.0);
+        let y_end = to.y().min(self.height as f32);
+        if y_start >= y_end {
+            return;
+        }
+
+        let dxdy = (to.x() - from.x()) / (to.y() - from.y());
+        let width = self.width as f32;
+        let mut x = from.x() + (y_start - from.y()) * dxdy;
+        let rows = (y_start as usize)..(f32::ceil(y_end) as usize);
+        self.rows_since_budget_check += rows.len();
+        if self.rows_since_budget_check >= BUDGET_CHECK_INTERVAL {
+            self.rows_since_budget_check = 0;
+            self.status = self.budget.check();
+        }
+        for y in rows {
+            let dy = ((y + 1) as f32).min(y_end) - (y as f32).max(y_start);
+            let x_next = x + dxdy * dy;
+            let d = dy * direction;
+
+            // Anything to the left of the canvas covers the first column; anything to the right
+            // lands in the extra cells and is never seen.
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let (x0, x1) = (x0.max(0.0).min(width), x1.max(0.0).min(width));
+
+            let row_start = y * self.stride;
+            let row = &mut self.accumulation[row_start..(row_start + self.stride)];
+            let x0_floor = x0.floor();
+            let x0i = x0_floor as usize;
+            let x1_ceil = x1.ceil();
+            let x1i = x1_ceil as usize;
+            if x1i <= x0i + 1 {
+                let x_mid = 0.5 * (x0 + x1) - x0_floor;
+                row[x0i] += d - d * x_mid;
+                row[x0i + 1] += d * x_mid;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0_fract = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0_fract) * (1.0 - x0_fract);
+                let x1_fract = x1 - x1_ceil + 1.0;
+                let am = 0.5 * s * x1_fract * x1_fract;
+                row[x0i] += d * a0;
+                if x1i == x0i + 2 {
+                    row[x0i + 1] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - x0_fract);
+                    row[x0i + 1] += d * (a1 - a0);
+                    for cell in &mut row[(x0i + 2)..(x1i - 1)] {
+                        *cell += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    row[x1i - 1] += d * (1.0 - a2 - am);
+                }
+                row[x1i] += d * am;
+            }
+
+            x = x_next;
+        }
+    }
+
+    fn write_to(&self, canvas: &mut Canvas) {
+        let bytes_per_pixel = canvas.format.bytes_per_pixel() as usize;
+        for y in 0..self.height {
+            let row = &self.accumulation[(y * self.stride)..((y + 1) * self.stride)];
+            let mut accumulator = 0.0;
+            for (x, &area) in row[..self.width].iter().enumerate() {
+                accumulator += area;
+                let coverage = (accumulator.abs().min(1.0) * 255.0).round() as u8;
+                let pixel_start = y * canvas.stride + x * bytes_per_pixel;
+                let pixel = &mut canvas.pixels[pixel_start..(pixel_start + bytes_per_pixel)];
+                match canvas.format {
+                    Format::A8 | Format::Rgb24 | Format::Rgba32 => {
+                        for channel in pixel {
+                            *channel = coverage;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl OutlineSink for Rasterizer {
+    fn move_to(&mut self, to: Vector2F) {
+        self.close();
+        let to = self.transform * to;
+        self.first_position = to;
+        self.current_position = to;
+    }
+
+    fn line_to(&mut self, to: Vector2F) {
+        let to = self.transform * to;
+        self.draw_line(self.current_position, to);
+        self.current_position = to;
+    }
+
+    fn quadratic_curve_to(&mut self, ctrl: Vector2F, to: Vector2F) {
+        let (p0, p1, p2) = (
+            self.current_position,
+            self.transform * ctrl,
+            self.transform * to,
+        );
+        let segment_count = curve_segment_count((p0 - p1 * 2.0 + p2).length() * 0.125);
+        let mut previous = p0;
+        for segment_index in 1..segment_count {
+            let t = segment_index as f32 / segment_count as f32;
+            let next = p0.lerp(p1, t).lerp(p1.lerp(p2, t), t);
+            self.draw_line(previous, next);
+            previous = next;
+        }
+        self.draw_line(previous, p2);
+        self.current_position = p2;
+    }
+
+    fn cubic_curve_to(&mut self, ctrl: LineSegment2F, to: Vector2F) {
+        let (p0, p1, p2, p3) = (
+            self.current_position,
+            self.transform * ctrl.from(),
+            self.transform * ctrl.to(),
+            self.transform * to,
+        );
+        let deviation = f32::max((p0 - p1 * 2.0 + p2).length(), (p1 - p2 * 2.0 + p3).length());
+        let segment_count = curve_segment_count(deviation * 0.75);
+        let mut previous = p0;
+        for segment_index in 1..segment_count {
+            let t = segment_index as f32 / segment_count as f32;
+            let (p01, p12, p23) = (p0.lerp(p1, t), p1.lerp(p2, t), p2.lerp(p3, t));
+            let next = p01.lerp(p12, t).lerp(p12.lerp(p23, t), t);
+            self.draw_line(previous, next);
+            previous = next;
+        }
+        self.draw_line(previous, p3);
+        self.current_position = p3;
+    }
+
+    fn close(&mut self) {
+        if self.current_position != self.first_position {
+            self.draw_line(self.current_position, self.first_position);
+            self.current_position = self.first_position;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{Vector2F, Vector2I};
+
+    use super::Rasterizer;
+    use crate::canvas::{CancellationToken, Canvas, Format, RasterizationBudget};
+    use crate::error::GlyphLoadingError;
+    use crate::outline::OutlineSink;
+
+    #[test]
+    fn test_rasterize_rect() {
+        let mut rasterizer = Rasterizer::new(
+            Vector2I::new(4, 2),
+            Transform2F::default(),
+            RasterizationBudget::new(),
+        );
+        rasterizer.move_to(Vector2F::new(0.5, 0.0));
+        rasterizer.line_to(Vector2F::new(3.0, 0.0));
+        rasterizer.line_to(Vector2F::new(3.0, 1.0));
+        rasterizer.line_to(Vector2F::new(0.5, 1.0));
+        rasterizer.close();
+
+        let mut canvas = Canvas::new(Vector2I::new(4, 2), Format::A8);
+        rasterizer.write_to(&mut canvas);
+        assert_eq!(canvas.pixels, [128, 255, 255, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_rasterize_clipped_rect() {
+        let mut rasterizer = Rasterizer::new(
+            Vector2I::new(2, 2),
+            Transform2F::default(),
+            RasterizationBudget::new(),
+        );
+        rasterizer.move_to(Vector2F::new(-4.0, -4.0));
+        rasterizer.line_to(Vector2F::new(8.0, -4.0));
+        rasterizer.line_to(Vector2F::new(8.0, 8.0));
+        rasterizer.line_to(Vector2F::new(-4.0, 8.0));
+        rasterizer.close();
+
+        let mut canvas = Canvas::new(Vector2I::new(2, 2), Format::Rgb24);
+        rasterizer.write_to(&mut canvas);
+        assert!(canvas.pixels.iter().all(|&value| value == 255));
+    }
+
+    #[test]
+    fn test_rasterize_cancelled() {
+        let cancellation_token = CancellationToken::new();
+        let budget = RasterizationBudget::new().with_cancellation_token(cancellation_token.clone());
+        let mut rasterizer =
+            Rasterizer::new(Vector2I::new(4, 4096), Transform2F::default(), budget);
+        rasterizer.move_to(Vector2F::new(0.0, 0.0));
+        rasterizer.line_to(Vector2F::new(4.0, 0.0));
+        rasterizer.line_to(Vector2F::new(4.0, 4096.0));
+        assert_eq!(rasterizer.status, Ok(()));
+
+        cancellation_token.cancel();
+        rasterizer.line_to(Vector2F::new(0.0, 4096.0));
+        rasterizer.close();
+        assert_eq!(rasterizer.status, Err(GlyphLoadingError::Cancelled));
+    }
+}
@@ -10,15 +10,19 @@
 
 //! A database of installed fonts that can be queried.
 
+use crate::classification::{ClassificationQuery, Panose};
 use crate::error::SelectionError;
 use crate::family::Family;
 use crate::family_handle::FamilyHandle;
 use crate::family_name::FamilyName;
 use crate::font::Font;
 use crate::handle::Handle;
+use crate::loader::Loader;
 use crate::matching;
-use crate::properties::Properties;
+use crate::properties::{Properties, Requirements};
+use crate::substitution::{self, Substitution, SubstitutionReason};
 use std::any::Any;
+use std::slice;
 
 #[cfg(all(
     any(target_os = "macos", target_os = "ios"),
@@ -104,6 +108,147 @@ pub trait Source: Any {
         Err(SelectionError::NotFound)
     }
 
+    /// Selects the fonts whose classification matches the query, e.g. serif faces with old-style
+    /// serifs.
+    ///
+    /// If the query has PANOSE numbers, the fonts are ordered from the most similar to the least,
+    /// followed by those whose PANOSE numbers can't be compared. This is useful for substituting
+    /// a face that looks like the one a document asks for when that one isn't installed.
+    ///
+    /// The default implementation does a brute-force search of installed fonts.
+    fn select_by_classification(
+        &self,
+        query: &ClassificationQuery,
+    ) -> Result<Vec<Handle>, SelectionError> {
+        let mut matches = vec![];
+        for handle in self.all_fonts()? {
+            let font = match Font::from_handle(&handle) {
+                Ok(font) => font,
+                Err(e) => {
+                    log::warn!("Error loading font from handle: {:?}", e);
+                    continue;
+                }
+            };
+            if let Some(classification) = font.classification() {
+                if query.matches(&classification) {
+                    let distance = query
+                        .panose
+                        .and_then(|panose| panose.distance(&classification.panose));
+                    matches.push((handle, distance));
+                }
+            }
+        }
+        if matches.is_empty() {
+            return Err(SelectionError::NotFound);
+        }
+        matches.sort_by_key(|&(_, distance)| (distance.is_none(), distance));
+        Ok(matches.into_iter().map(|(handle, _)| handle).collect())
+    }
+
+    /// Finds the closest installed replacement for a font family that may be missing, e.g. one
+    /// that a document asks for, and returns it along with how it was found.
+    ///
+    /// This is equivalent to `substitute_with_panose()` without PANOSE numbers.
+    #[inline]
+    fn substitute(
+        &self,
+        family_name: &str,
+        properties: &Properties,
+    ) -> Result<Substitution, SelectionError> {
+        self.substitute_with_panose(family_name, properties, None)
+    }
+
+    /// Finds the closest installed replacement for a font family that may be missing, using the
+    /// PANOSE numbers of the missing family if known (documents such as PDFs often record them),
+    /// and returns it along with how it was found.
+    ///
+    /// Candidates are tried in this order, and the face in the first installed family that best
+    /// matches `properties` is returned:
+    ///
+    /// 1. The family itself.
+    ///
+    /// 2. Families that are metric-compatible with it, from a built-in table: e.g. Liberation
+    ///    Sans for Arial.
+    ///
+    /// 3. The substitutes configured on the platform: Fontconfig aliases, the Windows
+    ///    `FontSubstitutes` registry key, or the family of the font that Core Text finds by
+    ///    PostScript or full name.
+    ///
+    /// 4. If `panose` is supplied, the installed family that is most similar to it. This searches
+    ///    every installed font, so it can be slow.
+    ///
+    /// 5. The default font for the generic family that the PANOSE numbers or the name suggest:
+    ///    e.g. the cursive family for a handwritten face.
+    fn substitute_with_panose(
+        &self,
+        family_name: &str,
+        properties: &Properties,
+        panose: Option<Panose>,
+    ) -> Result<Substitution, SelectionError> {
+        let select = |family_name: &str| {
+            self.select_best_match(&[FamilyName::Title(family_name.to_owned())], properties)
+        };
+
+        if let Ok(handle) = select(family_name) {
+            return Ok(Substitution {
+                handle,
+                reason: SubstitutionReason::Exact,
+            });
+        }
+
+        for &substitute_name in substitution::metric_compatible_families(family_name) {
+            if let Ok(handle) = select(substitute_name) {
+                return Ok(Substitution {
+                    handle,
+                    reason: SubstitutionReason::MetricCompatible(substitute_name.to_owned()),
+                });
+            }
+        }
+
+        for substitute_name in self.select_platform_substitutes(family_name) {
+            if let Ok(handle) = select(&substitute_name) {
+                return Ok(Substitution {
+                    handle,
+                    reason: SubstitutionReason::Platform(substitute_name),
+                });
+            }
+        }
+
+        if let Some(panose) = panose {
+            let handles = self
+                .select_by_classification(ClassificationQuery::new().panose(panose))
+                .unwrap_or_default();
+            let closest = handles.first().and_then(|handle| {
+                let font = Font::from_handle(handle).ok()?;
+                let distance = panose.distance(&font.classification()?.panose)?;
+                Some((handle, font.family_name(), distance))
+            });
+            if let Some((closest_handle, substitute_name, distance)) = closest {
+                return Ok(Substitution {
+                    handle: select(&substitute_name).unwrap_or_else(|_| closest_handle.clone()),
+                    reason: SubstitutionReason::Similar {
+                        family_name: substitute_name,
+                        distance,
+                    },
+                });
+            }
+        }
+
+        let generic_name = substitution::generic_family(family_name, panose.as_ref());
+        let handle = self.select_best_match(slice::from_ref(&generic_name), properties)?;
+        Ok(Substitution {
+            handle,
+            reason: SubstitutionReason::Generic(generic_name),
+        })
+    }
+
+    /// Returns the names of the families that the platform's font configuration substitutes for
+    /// the given family, best first.
+    #[doc(hidden)]
+    fn select_platform_substitutes(&self, _family_name: &str) -> Vec<String> {
+        vec![]
+    }
+
     // FIXME(pcwalton): This only returns one family instead of multiple families for the generic
     // family names.
     #[doc(hidden)]
@@ -128,31 +273,56 @@ pub trait Source: Any {
         &self,
         family_names: &[FamilyName],
         properties: &Properties,
+    ) -> Result<Handle, SelectionError> {
+        self.select_best_match_with_requirements(family_names, properties, &Requirements::new())
+    }
+
+    /// Performs font matching according to the CSS Fonts Level 3 specification, considering only
+    /// the faces that satisfy the given requirements, and returns the handle.
+    ///
+    /// This is useful when a face missing some capability can't be compensated for; e.g. a
+    /// renderer that refuses to synthesize small caps can require `Features::SMALL_CAPS` so that
+    /// a family's faces without an `smcp` feature are skipped.
+    fn select_best_match_with_requirements(
+        &self,
+        family_names: &[FamilyName],
+        properties: &Properties,
+        requirements: &Requirements,
     ) -> Result<Handle, SelectionError> {
         for family_name in family_names {
             if let Ok(family_handle) = self.select_family_by_generic_name(family_name) {
-                let candidates = self.select_descriptions_in_family(&family_handle)?;
+                let (handles, candidates): (Vec<Handle>, Vec<Properties>) = self
+                    .select_candidates_in_family(&family_handle, requirements)?
+                    .into_iter()
+                    .unzip();
                 if let Ok(index) = matching::find_best_match(&candidates, properties) {
-                    return Ok(family_handle.fonts[index].clone());
+                    return Ok(handles[index].clone());
                 }
             }
         }
         Err(SelectionError::NotFound)
     }
 
+    /// Loads each font in the family once and returns the handles and properties of those that
+    /// satisfy the requirements. Fonts that fail to load are skipped.
     #[doc(hidden)]
-    fn select_descriptions_in_family(
+    fn select_candidates_in_family(
         &self,
         family: &FamilyHandle,
-    ) -> Result<Vec<Properties>, SelectionError> {
-        let mut fields = vec![];
+        requirements: &Requirements,
+    ) -> Result<Vec<(Handle, Properties)>, SelectionError> {
+        let mut candidates = vec![];
         for font_handle in family.fonts() {
             match Font::from_handle(font_handle) {
-                Ok(font) => fields.push(font.properties()),
+                Ok(font) => {
+                    if requirements.is_satisfied_by(&font) {
+                        candidates.push((font_handle.clone(), font.properties()));
+                    }
+                }
                 Err(e) => log::warn!("Error loading font from handle: {:?}", e),
             }
         }
-        Ok(fields)
+        Ok(candidates)
     }
 
     /// Accesses this `Source` as `Any`, which allows downcasting back to a concrete type from a
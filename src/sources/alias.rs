@@ -0,0 +1,296 @@
+// font-kit/src/sources/alias.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A source that maps the names of missing families to families in another source.
+//!
+//! This is useful for rendering documents that ask for fonts that aren't installed, e.g. Windows
+//! fonts on Linux, without relying on the platform's font configuration. It also makes tests and
+//! packaged applications independent of that configuration.
+
+use crate::classification::Panose;
+use crate::error::SelectionError;
+use crate::family_handle::FamilyHandle;
+use crate::family_name::FamilyName;
+use crate::handle::Handle;
+use crate::properties::Properties;
+use crate::source::Source;
+use crate::substitution::{self, Substitution, SubstitutionReason};
+use std::any::Any;
+
+/// A source that maps the names of missing families to families in another source.
+///
+/// Aliases only apply when the requested family isn't in the wrapped source: a source that
+/// contains Arial returns Arial for "Arial", even if Arial is aliased to Liberation Sans.
+#[allow(missing_debug_implementations)]
+pub struct AliasSource {
+    source: Box<dyn Source>,
+    aliases: Vec<Alias>,
+}
+
+/// A family name and the families that stand in for it, best first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alias {
+    /// The name of the requested family.
+    pub family_name: String,
+    /// The names of the families that stand in for it, best first.
+    pub target_family_names: Vec<String>,
+    /// True if the target families have the same glyph advances as the requested family, so text
+    /// laid out for it will fit.
+    pub metric_compatible: bool,
+}
+
+impl AliasSource {
+    /// Creates a new source that contains the fonts of the given source, with no aliases.
+    pub fn new(source: Box<dyn Source>) -> AliasSource {
+        AliasSource {
+            source,
+            aliases: vec![],
+        }
+    }
+
+    /// Creates a new source that contains the fonts of the given source, with aliases for common
+    /// families to the families that are metric-compatible with them: e.g. Liberation Sans, Arimo,
+    /// and Helvetica for Arial.
+    ///
+    /// This is the same table that `Source::substitute()` uses.
+    pub fn with_metric_compatible_aliases(source: Box<dyn Source>) -> AliasSource {
+        let mut alias_source = AliasSource::new(source);
+        for (family_name, target_family_names) in substitution::all_metric_compatible_families() {
+            alias_source.add_metric_compatible_alias(family_name, target_family_names);
+        }
+        alias_source
+    }
+
+    /// Maps the given family name to the given families, best first, replacing any existing
+    /// alias for it.
+    ///
+    /// Family names are compared case-insensitively.
+    #[inline]
+    pub fn add_alias(&mut self, family_name: &str, target_family_names: &[&str]) {
+        self.insert_alias(family_name, target_family_names, false)
+    }
+
+    /// Like `add_alias()`, but also records that the target families are metric-compatible with
+    /// the requested family.
+    #[inline]
+    pub fn add_metric_compatible_alias(&mut self, family_name: &str, target_family_names: &[&str]) {
+        self.insert_alias(family_name, target_family_names, true)
+    }
+
+    fn insert_alias(
+        &mut self,
+        family_name: &str,
+        target_family_names: &[&str],
+        metric_compatible: bool,
+    ) {
+        let alias = Alias {
+            family_name: family_name.to_owned(),
+            target_family_names: target_family_names
+                .iter()
+                .map(|&target_family_name| target_family_name.to_owned())
+                .collect(),
+            metric_compatible,
+        };
+        match self
+            .aliases
+            .iter_mut()
+            .find(|existing| existing.family_name.eq_ignore_ascii_case(family_name))
+        {
+            Some(existing) => *existing = alias,
+            None => self.aliases.push(alias),
+        }
+    }
+
+    /// Removes the alias for the given family name, if any, and returns it.
+    pub fn remove_alias(&mut self, family_name: &str) -> Option<Alias> {
+        let index = self
+            .aliases
+            .iter()
+            .position(|alias| alias.family_name.eq_ignore_ascii_case(family_name))?;
+        Some(self.aliases.remove(index))
+    }
+
+    /// Returns the alias for the given family name, if any.
+    pub fn alias(&self, family_name: &str) -> Option<&Alias> {
+        self.aliases
+            .iter()
+            .find(|alias| alias.family_name.eq_ignore_ascii_case(family_name))
+    }
+
+    /// Returns all the aliases, in the order they were added.
+    #[inline]
+    pub fn aliases(&self) -> &[Alias] {
+        &self.aliases
+    }
+
+    /// Returns the wrapped source.
+    #[inline]
+    pub fn source(&self) -> &dyn Source {
+        &*self.source
+    }
+
+    /// Returns the wrapped source, discarding the aliases.
+    #[inline]
+    pub fn into_source(self) -> Box<dyn Source> {
+        self.source
+    }
+
+    /// Returns paths of all fonts in the wrapped source.
+    #[inline]
+    pub fn all_fonts(&self) -> Result<Vec<Handle>, SelectionError> {
+        self.source.all_fonts()
+    }
+
+    /// Returns the names of all families in the wrapped source, followed by the names of the
+    /// aliased families that resolve to one of them.
+    pub fn all_families(&self) -> Result<Vec<String>, SelectionError> {
+        let mut families = self.source.all_families()?;
+        for alias in &self.aliases {
+            let is_new = !families
+                .iter()
+                .any(|family| family.eq_ignore_ascii_case(&alias.family_name));
+            if is_new && self.resolve_alias(alias).is_some() {
+                families.push(alias.family_name.clone());
+            }
+        }
+        Ok(families)
+    }
+
+    /// Looks up a font family by name and returns the handles of all the fonts in that family.
+    ///
+    /// If the wrapped source doesn't contain the family, the first target of its alias that it
+    /// does contain is returned instead.
+    pub fn select_family_by_name(&self, family_name: &str) -> Result<FamilyHandle, SelectionError> {
+        match self.source.select_family_by_name(family_name) {
+            Err(SelectionError::NotFound) => {}
+            result => return result,
+        }
+        self.alias(family_name)
+            .and_then(|alias| self.resolve_alias(alias))
+            .ok_or(SelectionError::NotFound)
+    }
+
+    /// Selects a font by PostScript name, which should be a unique identifier.
+    #[inline]
+    pub fn select_by_postscript_name(
+        &self,
+        postscript_name: &str,
+    ) -> Result<Handle, SelectionError> {
+        self.source.select_by_postscript_name(postscript_name)
+    }
+
+    /// Performs font matching according to the CSS Fonts Level 3 specification and returns the
+    /// handle.
+    #[inline]
+    pub fn select_best_match(
+        &self,
+        family_names: &[FamilyName],
+        properties: &Properties,
+    ) -> Result<Handle, SelectionError> {
+        <Self as Source>::select_best_match(self, family_names, properties)
+    }
+
+    /// Finds the closest replacement for a font family that may be missing, like
+    /// `Source::substitute_with_panose()`, trying the aliases of the family after the family
+    /// itself.
+    pub fn substitute_with_panose(
+        &self,
+        family_name: &str,
+        properties: &Properties,
+        panose: Option<Panose>,
+    ) -> Result<Substitution, SelectionError> {
+        let select = |family_name: &str| {
+            self.source
+                .select_best_match(&[FamilyName::Title(family_name.to_owned())], properties)
+        };
+
+        if let Ok(handle) = select(family_name) {
+            return Ok(Substitution {
+                handle,
+                reason: SubstitutionReason::Exact,
+            });
+        }
+
+        if let Some(alias) = self.alias(family_name) {
+            for target_family_name in &alias.target_family_names {
+                if let Ok(handle) = select(target_family_name) {
+                    let reason = if alias.metric_compatible {
+                        SubstitutionReason::MetricCompatible(target_family_name.clone())
+                    } else {
+                        SubstitutionReason::Alias(target_family_name.clone())
+                    };
+                    return Ok(Substitution { handle, reason });
+                }
+            }
+        }
+
+        self.source
+            .substitute_with_panose(family_name, properties, panose)
+    }
+
+    fn resolve_alias(&self, alias: &Alias) -> Option<FamilyHandle> {
+        alias
+            .target_family_names
+            .iter()
+            .find_map(|target_family_name| {
+                self.source.select_family_by_name(target_family_name).ok()
+            })
+    }
+}
+
+impl Source for AliasSource {
+    #[inline]
+    fn all_fonts(&self) -> Result<Vec<Handle>, SelectionError> {
+        self.all_fonts()
+    }
+
+    #[inline]
+    fn all_families(&self) -> Result<Vec<String>, SelectionError> {
+        self.all_families()
+    }
+
+    #[inline]
+    fn list_fonts(&self) -> Result<Vec<Handle>, SelectionError> {
+        self.source.list_fonts()
+    }
+
+    #[inline]
+    fn select_family_by_name(&self, family_name: &str) -> Result<FamilyHandle, SelectionError> {
+        self.select_family_by_name(family_name)
+    }
+
+    #[inline]
+    fn select_by_postscript_name(&self, postscript_name: &str) -> Result<Handle, SelectionError> {
+        self.select_by_postscript_name(postscript_name)
+    }
+
+    #[inline]
+    fn substitute_with_panose(
+        &self,
+        family_name: &str,
+        properties: &Properties,
+        panose: Option<Panose>,
+    ) -> Result<Substitution, SelectionError> {
+        self.substitute_with_panose(family_name, properties, panose)
+    }
+
+    #[inline]
+    fn select_platform_substitutes(&self, family_name: &str) -> Vec<String> {
+        self.source.select_platform_substitutes(family_name)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -128,6 +128,29 @@ impl Source for CoreTextSource {
         self.select_by_postscript_name(postscript_name)
     }
 
+    fn select_platform_substitutes(&self, family_name: &str) -> Vec<String> {
+        // Core Text resolves PostScript and full names as well as family names, so a name such as
+        // "Arial Bold" is substituted with its family. For any other name that isn't installed,
+        // it returns its last-resort fallback font, which isn't a substitute for this family in
+        // particular.
+        let core_text_font = match core_text::font::new_from_name(family_name, 12.0) {
+            Ok(core_text_font) => core_text_font,
+            Err(_) => return vec![],
+        };
+        let is_requested_font = core_text_font
+            .postscript_name()
+            .eq_ignore_ascii_case(family_name)
+            || core_text_font
+                .display_name()
+                .eq_ignore_ascii_case(family_name);
+        let substitute_name = core_text_font.family_name();
+        if is_requested_font && !substitute_name.eq_ignore_ascii_case(family_name) {
+            vec![substitute_name]
+        } else {
+            vec![]
+        }
+    }
+
     #[inline]
     fn as_any(&self) -> &dyn Any {
         self
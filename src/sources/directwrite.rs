@@ -13,6 +13,14 @@
 use dwrote::Font as DWriteFont;
 use dwrote::FontCollection as DWriteFontCollection;
 use std::any::Any;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use winapi::ctypes::c_void;
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::winnt::LONG;
+use winapi::um::winreg::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
 
 use crate::error::SelectionError;
 use crate::family_handle::FamilyHandle;
@@ -21,6 +29,9 @@ use crate::handle::Handle;
 use crate::properties::Properties;
 use crate::source::Source;
 
+const FONT_SUBSTITUTES_KEY: &str =
+    "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\FontSubstitutes";
+
 /// A source that contains the installed fonts on Windows.
 #[allow(missing_debug_implementations)]
 pub struct DirectWriteSource {
@@ -125,6 +136,19 @@ impl Source for DirectWriteSource {
         self.select_family_by_name(family_name)
     }
 
+    fn select_platform_substitutes(&self, family_name: &str) -> Vec<String> {
+        // Value names may carry a character set after a comma, e.g. `Helv,0`.
+        for value_name in &[family_name.to_owned(), format!("{},0", family_name)] {
+            if let Some(substitute) = read_font_substitute(value_name) {
+                let substitute_name = substitute.split(',').next().unwrap_or("").trim();
+                if !substitute_name.is_empty() && substitute_name != family_name {
+                    return vec![substitute_name.to_owned()];
+                }
+            }
+        }
+        vec![]
+    }
+
     #[inline]
     fn as_any(&self) -> &dyn Any {
         self
@@ -135,3 +159,49 @@ impl Source for DirectWriteSource {
         self
     }
 }
+
+// Reads a value from the `FontSubstitutes` registry key, which maps missing family names to
+// installed ones.
+fn read_font_substitute(value_name: &str) -> Option<String> {
+    let key_name: Vec<u16> = OsStr::new(FONT_SUBSTITUTES_KEY)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let value_name: Vec<u16> = OsStr::new(value_name)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    unsafe {
+        let mut size: DWORD = 0;
+        let result = RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key_name.as_ptr(),
+            value_name.as_ptr(),
+            RRF_RT_REG_SZ,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut size,
+        );
+        if result != ERROR_SUCCESS as LONG {
+            return None;
+        }
+
+        let mut data: Vec<u16> = vec![0; size as usize / 2];
+        let result = RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key_name.as_ptr(),
+            value_name.as_ptr(),
+            RRF_RT_REG_SZ,
+            ptr::null_mut(),
+            data.as_mut_ptr() as *mut c_void,
+            &mut size,
+        );
+        if result != ERROR_SUCCESS as LONG {
+            return None;
+        }
+
+        // The size includes the terminating null.
+        data.truncate((size as usize / 2).saturating_sub(1));
+        Some(String::from_utf16_lossy(&data))
+    }
+}
@@ -229,6 +229,28 @@ impl Source for FontconfigSource {
         self.select_by_postscript_name(postscript_name)
     }
 
+    fn select_platform_substitutes(&self, family_name: &str) -> Vec<String> {
+        // Fontconfig appends a generic family to every family it has no rule for, and then expands
+        // the generic family into its preferred families. Those aren't substitutes for this
+        // family in particular, so drop them and keep only the families that the rules add.
+        let generic_families: Vec<String> =
+            ["serif", "sans-serif", "monospace", "cursive", "fantasy"]
+                .iter()
+                .flat_map(|&generic_name| configured_families(generic_name))
+                .collect();
+
+        let mut substitutes = vec![];
+        for substitute_name in configured_families(family_name) {
+            if !generic_families.contains(&substitute_name)
+                && !substitute_name.eq_ignore_ascii_case(family_name)
+                && !substitutes.contains(&substitute_name)
+            {
+                substitutes.push(substitute_name);
+            }
+        }
+        substitutes
+    }
+
     #[inline]
     fn as_any(&self) -> &dyn Any {
         self
@@ -240,6 +262,14 @@ impl Source for FontconfigSource {
     }
 }
 
+// Returns the family list that the Fontconfig configuration rules produce for the family.
+fn configured_families(family_name: &str) -> Vec<String> {
+    let mut pattern = fc::Pattern::new();
+    pattern.push_string(fc::Object::Family, family_name.to_owned());
+    pattern.config_substitute(fc::MatchKind::Pattern);
+    pattern.get_strings(fc::Object::Family)
+}
+
 // A minimal fontconfig wrapper.
 mod fc {
     #![allow(dead_code)]
@@ -418,6 +448,34 @@ mod fc {
             }
         }
 
+        // FcPatternGetString, for each value of the object in turn
+        pub fn get_strings(&self, object: Object) -> Vec<String> {
+            let mut strings = vec![];
+            loop {
+                unsafe {
+                    let mut string = ptr::null_mut();
+                    let res = ffi_dispatch!(
+                        feature = "source-fontconfig-dlopen",
+                        LIB,
+                        FcPatternGetString,
+                        self.d,
+                        object.as_ptr(),
+                        strings.len() as i32,
+                        &mut string
+                    );
+                    if res != ffi::FcResultMatch || string.is_null() {
+                        return strings;
+                    }
+
+                    strings.push(
+                        CStr::from_ptr(string as *const c_char)
+                            .to_string_lossy()
+                            .into_owned(),
+                    );
+                }
+            }
+        }
+
         // FcFontSort
         pub fn sorted(&self, config: &Config) -> Result<FontSet, Error> {
             let mut res = ffi::FcResultMatch;
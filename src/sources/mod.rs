@@ -12,7 +12,9 @@
 //!
 //! The system-specific sources (Core Text, DirectWrite, and Fontconfig) contain the fonts that are
 //! installed on the system. The remaining databases (`fs`, `mem`, and `multi`) allow `font-kit` to
-//! query fonts not installed on the system.
+//! query fonts not installed on the system, and `alias` renames the families of another source.
+
+pub mod alias;
 
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 pub mod core_text;
@@ -145,6 +145,14 @@ impl Source for MultiSource {
         self.select_by_postscript_name(postscript_name)
     }
 
+    fn select_platform_substitutes(&self, family_name: &str) -> Vec<String> {
+        let mut substitutes = vec![];
+        for subsource in &self.subsources {
+            substitutes.extend(subsource.select_platform_substitutes(family_name))
+        }
+        substitutes
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
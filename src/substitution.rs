@@ -0,0 +1,223 @@
+// font-kit/src/substitution.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Substitution of installed fonts for fonts that a document asks for but that are missing.
+//!
+//! See `Source::substitute()`.
+
+use crate::classification::{Panose, PanoseFamilyKind, SerifStyle};
+use crate::family_name::FamilyName;
+use crate::handle::Handle;
+
+/// An installed font chosen in place of a requested font family, and why it was chosen.
+#[derive(Clone, Debug)]
+pub struct Substitution {
+    /// The font that was selected.
+    pub handle: Handle,
+    /// How the font was found.
+    pub reason: SubstitutionReason,
+}
+
+/// How a substitute font was found, from the most faithful to the least.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubstitutionReason {
+    /// The requested family is installed.
+    Exact,
+    /// The requested family is missing, but this family, which has the same glyph advances, is
+    /// installed. Text laid out for the requested family will fit.
+    MetricCompatible(String),
+    /// The requested family is missing, and an `AliasSource` maps it to this family.
+    Alias(String),
+    /// This family is the installed family whose PANOSE numbers are closest to the ones supplied.
+    Similar {
+        /// The name of the family.
+        family_name: String,
+        /// The PANOSE distance from the supplied numbers. See `Panose::distance()`.
+        distance: u32,
+    },
+    /// The platform's font configuration substitutes this family for the requested one: e.g.
+    /// Fontconfig aliases, the `FontSubstitutes` registry key on Windows, or the font that Core
+    /// Text falls back to.
+    Platform(String),
+    /// Nothing better was found, so the default font for this generic family was selected.
+    Generic(FamilyName),
+}
+
+// Families with the same advance widths, so they can stand in for each other without reflowing
+// text. Lookups are case-insensitive.
+static METRIC_COMPATIBLE_FAMILIES: &[(&str, &[&str])] = &[
+    ("Arial", &["Liberation Sans", "Arimo", "Helvetica"]),
+    (
+        "Arial Narrow",
+        &["Liberation Sans Narrow", "Nimbus Sans Narrow"],
+    ),
+    ("Arimo", &["Liberation Sans", "Arial"]),
+    (
+        "Book Antiqua",
+        &["Palatino Linotype", "TeX Gyre Pagella", "P052"],
+    ),
+    ("Bookman Old Style", &["TeX Gyre Bonum", "URW Bookman"]),
+    ("Caladea", &["Cambria"]),
+    ("Calibri", &["Carlito"]),
+    ("Cambria", &["Caladea"]),
+    ("Carlito", &["Calibri"]),
+    ("Century Schoolbook", &["TeX Gyre Schola", "C059"]),
+    (
+        "Courier",
+        &[
+            "Courier New",
+            "Liberation Mono",
+            "Cousine",
+            "Nimbus Mono PS",
+        ],
+    ),
+    ("Courier New", &["Liberation Mono", "Cousine", "Courier"]),
+    ("Cousine", &["Liberation Mono", "Courier New"]),
+    ("Georgia", &["Gelasio"]),
+    ("Gelasio", &["Georgia"]),
+    (
+        "Helvetica",
+        &[
+            "Arial",
+            "Liberation Sans",
+            "Arimo",
+            "Nimbus Sans",
+            "TeX Gyre Heros",
+        ],
+    ),
+    ("Liberation Mono", &["Cousine", "Courier New"]),
+    ("Liberation Sans", &["Arimo", "Arial"]),
+    ("Liberation Serif", &["Tinos", "Times New Roman"]),
+    (
+        "Palatino",
+        &["Palatino Linotype", "TeX Gyre Pagella", "P052"],
+    ),
+    (
+        "Palatino Linotype",
+        &["Palatino", "TeX Gyre Pagella", "P052"],
+    ),
+    (
+        "Times",
+        &[
+            "Times New Roman",
+            "Liberation Serif",
+            "Tinos",
+            "Nimbus Roman",
+        ],
+    ),
+    ("Times New Roman", &["Liberation Serif", "Tinos", "Times"]),
+    ("Tinos", &["Liberation Serif", "Times New Roman"]),
+    ("Zapf Chancery", &["Z003", "TeX Gyre Chorus"]),
+];
+
+/// Returns the families that are metric-compatible with the given family, best first.
+pub(crate) fn metric_compatible_families(family_name: &str) -> &'static [&'static str] {
+    METRIC_COMPATIBLE_FAMILIES
+        .iter()
+        .find(|&&(name, _)| name.eq_ignore_ascii_case(family_name))
+        .map_or(&[], |&(_, families)| families)
+}
+
+/// Returns every family in the built-in table of metric-compatible families, along with the
+/// families that are metric-compatible with it.
+pub(crate) fn all_metric_compatible_families(
+) -> impl Iterator<Item = (&'static str, &'static [&'static str])> {
+    METRIC_COMPATIBLE_FAMILIES.iter().cloned()
+}
+
+/// Guesses the generic family of a missing family from its PANOSE numbers, if known, or else from
+/// its name.
+pub(crate) fn generic_family(family_name: &str, panose: Option<&Panose>) -> FamilyName {
+    if let Some(panose) = panose {
+        match panose.family_kind() {
+            PanoseFamilyKind::LatinHandWritten => return FamilyName::Cursive,
+            PanoseFamilyKind::LatinDecorative => return FamilyName::Fantasy,
+            PanoseFamilyKind::LatinText if panose.is_monospace() => return FamilyName::Monospace,
+            _ => {}
+        }
+        match panose.serif_style() {
+            // Flared and rounded stroke ends have no serifs.
+            Some(SerifStyle::Flared) | Some(SerifStyle::Rounded) => return FamilyName::SansSerif,
+            Some(serif_style) => match serif_style.is_serif() {
+                Some(true) => return FamilyName::Serif,
+                Some(false) => return FamilyName::SansSerif,
+                None => {}
+            },
+            None => {}
+        }
+    }
+
+    let family_name = family_name.to_ascii_lowercase();
+    let contains_any = |keywords: &[&str]| {
+        keywords
+            .iter()
+            .any(|&keyword| family_name.contains(keyword))
+    };
+    if contains_any(&["mono", "courier", "consol", "typewriter", "code"]) {
+        FamilyName::Monospace
+    } else if contains_any(&["serif"]) && !contains_any(&["sans"]) {
+        FamilyName::Serif
+    } else if contains_any(&["sans", "gothic", "grotesk", "arial", "helvetica", "verdana"]) {
+        FamilyName::SansSerif
+    } else if contains_any(&["script", "hand", "brush", "chancery"]) {
+        FamilyName::Cursive
+    } else {
+        FamilyName::Serif
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{generic_family, metric_compatible_families};
+    use crate::classification::Panose;
+    use crate::family_name::FamilyName;
+
+    #[test]
+    fn test_metric_compatible_families() {
+        assert_eq!(
+            metric_compatible_families("times new roman"),
+            &["Liberation Serif", "Tinos", "Times"]
+        );
+        assert!(metric_compatible_families("EB Garamond 12").is_empty());
+    }
+
+    #[test]
+    fn test_generic_family() {
+        assert_eq!(generic_family("Consolas", None), FamilyName::Monospace);
+        assert_eq!(generic_family("Noto Sans", None), FamilyName::SansSerif);
+        assert_eq!(generic_family("Noto Serif", None), FamilyName::Serif);
+        assert_eq!(generic_family("Minion Pro", None), FamilyName::Serif);
+        let sans = Panose([2, 11, 6, 4, 2, 2, 2, 2, 2, 4]);
+        assert_eq!(
+            generic_family("Minion Pro", Some(&sans)),
+            FamilyName::SansSerif
+        );
+        let flared = Panose([2, 14, 5, 2, 2, 2, 2, 2, 2, 4]);
+        assert_eq!(
+            generic_family("Minion Pro", Some(&flared)),
+            FamilyName::SansSerif
+        );
+        let mono = Panose([2, 11, 6, 9, 2, 2, 2, 2, 2, 4]);
+        assert_eq!(
+            generic_family("Minion Pro", Some(&mono)),
+            FamilyName::Monospace
+        );
+        let script = Panose([3, 1, 1, 1, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(
+            generic_family("Minion Pro", Some(&script)),
+            FamilyName::Cursive
+        );
+        let decorative = Panose([4, 2, 7, 5, 4, 5, 2, 2, 2, 3]);
+        assert_eq!(
+            generic_family("Minion Pro", Some(&decorative)),
+            FamilyName::Fantasy
+        );
+    }
+}
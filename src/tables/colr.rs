@@ -0,0 +1,122 @@
+// font-kit/src/tables/colr.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The color table (`COLR`).
+//!
+//! Only the layered glyphs of version 0 are read. Version 1 tables also contain version 0 data,
+//! which fonts typically provide for glyphs that can be expressed that way.
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/colr
+
+use crate::color::{ColorLayer, Paint};
+use crate::tables;
+
+const BASE_GLYPH_RECORD_SIZE: usize = 6;
+const LAYER_RECORD_SIZE: usize = 4;
+const FOREGROUND_PALETTE_INDEX: u16 = 0xffff;
+
+/// Returns the layers of the given glyph, from bottom to top, or an empty list if the glyph
+/// isn't a layered color glyph.
+pub(crate) fn layers(table: &[u8], glyph_id: u32) -> Option<Vec<ColorLayer>> {
+    let base_glyph_count = tables::read_u16(table, 2)? as usize;
+    let base_glyph_records = table.get((tables::read_u32(table, 4)? as usize)..)?;
+    let layer_records = table.get((tables::read_u32(table, 8)? as usize)..)?;
+    let layer_count = tables::read_u16(table, 12)? as usize;
+    if glyph_id > u16::MAX as u32 {
+        return Some(vec![]);
+    }
+
+    // Base glyph records are sorted by glyph ID.
+    let (mut low, mut high) = (0, base_glyph_count);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let record_offset = mid * BASE_GLYPH_RECORD_SIZE;
+        let base_glyph_id = tables::read_u16(base_glyph_records, record_offset)? as u32;
+        if base_glyph_id < glyph_id {
+            low = mid + 1;
+        } else if base_glyph_id > glyph_id {
+            high = mid;
+        } else {
+            let first_layer_index = tables::read_u16(base_glyph_records, record_offset + 2)?;
+            let glyph_layer_count = tables::read_u16(base_glyph_records, record_offset + 4)?;
+            let layer_range = (first_layer_index as usize)
+                ..(first_layer_index as usize + glyph_layer_count as usize);
+            if layer_range.end > layer_count {
+                return None;
+            }
+            return layer_range
+                .map(|layer_index| {
+                    let record_offset = layer_index * LAYER_RECORD_SIZE;
+                    let glyph_id = tables::read_u16(layer_records, record_offset)? as u32;
+                    let paint = match tables::read_u16(layer_records, record_offset + 2)? {
+                        FOREGROUND_PALETTE_INDEX => Paint::Foreground,
+                        palette_index => Paint::PaletteIndex(palette_index),
+                    };
+                    Some(ColorLayer { glyph_id, paint })
+                })
+                .collect();
+        }
+    }
+    Some(vec![])
+}
+
+#[cfg(test)]
+mod test {
+    use super::layers;
+    use crate::color::{ColorLayer, Paint};
+
+    #[rustfmt::skip]
+    const TABLE: [u8; 38] = [
+        0x00, 0x00,             // version
+        0x00, 0x02,             // numBaseGlyphRecords
+        0x00, 0x00, 0x00, 0x0e, // baseGlyphRecordsOffset
+        0x00, 0x00, 0x00, 0x1a, // layerRecordsOffset
+        0x00, 0x03,             // numLayerRecords
+        // Base glyph records
+        0x00, 0x05, 0x00, 0x00, 0x00, 0x02,
+        0x00, 0x09, 0x00, 0x02, 0x00, 0x01,
+        // Layer records
+        0x00, 0x0a, 0x00, 0x01,
+        0x00, 0x0b, 0xff, 0xff,
+        0x00, 0x0c, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_layers() {
+        assert_eq!(
+            layers(&TABLE, 5),
+            Some(vec![
+                ColorLayer {
+                    glyph_id: 10,
+                    paint: Paint::PaletteIndex(1),
+                },
+                ColorLayer {
+                    glyph_id: 11,
+                    paint: Paint::Foreground,
+                },
+            ])
+        );
+        assert_eq!(
+            layers(&TABLE, 9),
+            Some(vec![ColorLayer {
+                glyph_id: 12,
+                paint: Paint::PaletteIndex(0),
+            }])
+        );
+        assert_eq!(layers(&TABLE, 6), Some(vec![]));
+        assert_eq!(layers(&TABLE, 0x10005), Some(vec![]));
+    }
+
+    #[test]
+    fn test_layers_malformed() {
+        assert_eq!(layers(&TABLE[..34], 9), None);
+        assert_eq!(layers(&TABLE[..10], 5), None);
+    }
+}
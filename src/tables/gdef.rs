@@ -0,0 +1,156 @@
+// font-kit/src/tables/gdef.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The glyph definition table (`GDEF`).
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/gdef
+
+use crate::layout::{GlyphClass, LigatureCaret};
+use crate::tables::{self, layout};
+
+const GLYPH_CLASS_DEF_OFFSET: usize = 4;
+const ATTACH_LIST_OFFSET: usize = 6;
+const LIG_CARET_LIST_OFFSET: usize = 8;
+const MARK_ATTACH_CLASS_DEF_OFFSET: usize = 10;
+
+/// Returns the class of a glyph, or `None` if it's unclassified.
+pub(crate) fn glyph_class(table: &[u8], glyph_id: u16) -> Option<GlyphClass> {
+    let class_def = tables::subtable(table, 0, GLYPH_CLASS_DEF_OFFSET)?;
+    match layout::glyph_class(class_def, glyph_id)? {
+        1 => Some(GlyphClass::Base),
+        2 => Some(GlyphClass::Ligature),
+        3 => Some(GlyphClass::Mark),
+        4 => Some(GlyphClass::Component),
+        _ => None,
+    }
+}
+
+/// Returns the mark attachment class of a glyph, or `None` if it has none.
+pub(crate) fn mark_attachment_class(table: &[u8], glyph_id: u16) -> Option<u16> {
+    let class_def = tables::subtable(table, 0, MARK_ATTACH_CLASS_DEF_OFFSET)?;
+    match layout::glyph_class(class_def, glyph_id)? {
+        0 => None,
+        class => Some(class),
+    }
+}
+
+/// Returns the indices of the contour points to which other glyphs attach, or `None` if the
+/// glyph has none.
+pub(crate) fn attachment_points(table: &[u8], glyph_id: u16) -> Option<Vec<u16>> {
+    let attach_list = tables::subtable(table, 0, ATTACH_LIST_OFFSET)?;
+    let attach_point = covered_subtable(attach_list, glyph_id)?;
+    let point_count = tables::read_u16(attach_point, 0)?;
+    (0..point_count as usize)
+        .map(|point_index| tables::read_u16(attach_point, 2 + point_index * 2))
+        .collect()
+}
+
+/// Returns the caret positions within a ligature glyph, or `None` if the glyph has none.
+pub(crate) fn ligature_carets(table: &[u8], glyph_id: u16) -> Option<Vec<LigatureCaret>> {
+    let lig_caret_list = tables::subtable(table, 0, LIG_CARET_LIST_OFFSET)?;
+    let lig_glyph = covered_subtable(lig_caret_list, glyph_id)?;
+    let caret_count = tables::read_u16(lig_glyph, 0)?;
+    (0..caret_count as usize)
+        .map(|caret_index| {
+            let caret_value = tables::subtable(lig_glyph, 0, 2 + caret_index * 2)?;
+            match tables::read_u16(caret_value, 0)? {
+                1 | 3 => Some(LigatureCaret::Coordinate(
+                    tables::read_i16(caret_value, 2)? as f32
+                )),
+                2 => Some(LigatureCaret::ContourPoint(tables::read_u16(
+                    caret_value,
+                    2,
+                )?)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// Looks up a glyph's subtable in the `AttachList` or `LigCaretList` format: a coverage table
+// followed by a count and an array of offsets indexed by coverage index.
+fn covered_subtable(list: &[u8], glyph_id: u16) -> Option<&[u8]> {
+    let coverage = tables::subtable(list, 0, 0)?;
+    let coverage_index = layout::coverage_index(coverage, glyph_id)??;
+    if coverage_index >= tables::read_u16(list, 2)? {
+        return None;
+    }
+    tables::subtable(list, 0, 4 + coverage_index as usize * 2)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{attachment_points, glyph_class, ligature_carets, mark_attachment_class};
+    use crate::layout::{GlyphClass, LigatureCaret};
+
+    #[rustfmt::skip]
+    const TABLE: [u8; 74] = [
+        0x00, 0x01, 0x00, 0x00, // version 1.0
+        0x00, 0x0c,             // glyphClassDefOffset
+        0x00, 0x18,             // attachListOffset
+        0x00, 0x2a,             // ligCaretListOffset
+        0x00, 0x40,             // markAttachClassDefOffset
+        // GlyphClassDef at 12
+        0x00, 0x01, 0x00, 0x05, 0x00, 0x03,
+        0x00, 0x01, 0x00, 0x02, 0x00, 0x03,
+        // AttachList at 24
+        0x00, 0x06,             // coverageOffset
+        0x00, 0x01,             // glyphCount
+        0x00, 0x0c,             // attachPointOffsets[0]
+        // Coverage at 30
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x05,
+        // AttachPoint at 36
+        0x00, 0x02, 0x00, 0x07, 0x00, 0x09,
+        // LigCaretList at 42
+        0x00, 0x06,             // coverageOffset
+        0x00, 0x01,             // ligGlyphCount
+        0x00, 0x0c,             // ligGlyphOffsets[0]
+        // Coverage at 48
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x06,
+        // LigGlyph at 54
+        0x00, 0x02, 0x00, 0x06, 0x00, 0x0a,
+        0x00, 0x01, 0xff, 0xf6, // CaretValue format 1: -10
+        // MarkAttachClassDef at 64, which doubles as CaretValue format 2: point 1
+        0x00, 0x02, 0x00, 0x01, 0x00, 0x07, 0x00, 0x07, 0x00, 0x07,
+    ];
+
+    #[test]
+    fn test_glyph_class() {
+        assert_eq!(glyph_class(&TABLE, 4), None);
+        assert_eq!(glyph_class(&TABLE, 5), Some(GlyphClass::Base));
+        assert_eq!(glyph_class(&TABLE, 6), Some(GlyphClass::Ligature));
+        assert_eq!(glyph_class(&TABLE, 7), Some(GlyphClass::Mark));
+        assert_eq!(glyph_class(&TABLE, 8), None);
+    }
+
+    #[test]
+    fn test_mark_attachment_class() {
+        assert_eq!(mark_attachment_class(&TABLE, 7), Some(7));
+        assert_eq!(mark_attachment_class(&TABLE, 6), None);
+    }
+
+    #[test]
+    fn test_attachment_points() {
+        assert_eq!(attachment_points(&TABLE, 5), Some(vec![7, 9]));
+        assert_eq!(attachment_points(&TABLE, 6), None);
+    }
+
+    #[test]
+    fn test_ligature_carets() {
+        assert_eq!(
+            ligature_carets(&TABLE, 6),
+            Some(vec![
+                LigatureCaret::Coordinate(-10.0),
+                LigatureCaret::ContourPoint(1),
+            ])
+        );
+        assert_eq!(ligature_carets(&TABLE, 5), None);
+    }
+}
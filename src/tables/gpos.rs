@@ -0,0 +1,196 @@
+// font-kit/src/tables/gpos.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The glyph positioning table (`GPOS`).
+//!
+//! Only the mark-to-base and mark-to-mark attachment lookups of the `mark` and `mkmk` features
+//! are read.
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/gpos
+
+use crate::tables::{self, layout};
+use pathfinder_geometry::vector::Vector2F;
+
+const FEATURE_LIST_OFFSET: usize = 6;
+const LOOKUP_LIST_OFFSET: usize = 8;
+const FEATURE_RECORD_SIZE: usize = 6;
+
+const FEATURE_TAG_MARK: u32 = 0x6d61726b; // 'mark'
+const FEATURE_TAG_MKMK: u32 = 0x6d6b6d6b; // 'mkmk'
+
+const LOOKUP_TYPE_MARK_TO_BASE: u16 = 4;
+const LOOKUP_TYPE_MARK_TO_MARK: u16 = 6;
+const LOOKUP_TYPE_EXTENSION: u16 = 9;
+
+/// Returns the offset from the origin of the base glyph to the origin of the mark glyph when the
+/// mark is attached to the base, or `None` if no mark attachment lookup applies to the pair or
+/// the table is malformed.
+///
+/// Lookups are tried in lookup list order, and the first subtable that covers both glyphs wins.
+pub(crate) fn mark_attachment_offset(
+    table: &[u8],
+    base_glyph_id: u16,
+    mark_glyph_id: u16,
+) -> Option<Vector2F> {
+    let lookup_list = tables::subtable(table, 0, LOOKUP_LIST_OFFSET)?;
+    for lookup_index in mark_lookup_indices(table)? {
+        let lookup = tables::subtable(lookup_list, 0, 2 + lookup_index as usize * 2)?;
+        let lookup_type = tables::read_u16(lookup, 0)?;
+        let subtable_count = tables::read_u16(lookup, 4)?;
+        for subtable_index in 0..subtable_count as usize {
+            let mut subtable = tables::subtable(lookup, 0, 6 + subtable_index * 2)?;
+            let mut subtable_type = lookup_type;
+            if lookup_type == LOOKUP_TYPE_EXTENSION {
+                subtable_type = tables::read_u16(subtable, 2)?;
+                subtable = subtable.get((tables::read_u32(subtable, 4)? as usize)..)?;
+            }
+            if subtable_type != LOOKUP_TYPE_MARK_TO_BASE
+                && subtable_type != LOOKUP_TYPE_MARK_TO_MARK
+            {
+                break;
+            }
+            if let Some(offset) = attachment_offset(subtable, base_glyph_id, mark_glyph_id)? {
+                return Some(offset);
+            }
+        }
+    }
+    None
+}
+
+// Returns the sorted indices of the lookups of the `mark` and `mkmk` features, for all scripts
+// and languages.
+fn mark_lookup_indices(table: &[u8]) -> Option<Vec<u16>> {
+    let feature_list = tables::subtable(table, 0, FEATURE_LIST_OFFSET)?;
+    let feature_count = tables::read_u16(feature_list, 0)?;
+    let mut lookup_indices = vec![];
+    for feature_index in 0..feature_count as usize {
+        let record_offset = 2 + feature_index * FEATURE_RECORD_SIZE;
+        let feature_tag = tables::read_u32(feature_list, record_offset)?;
+        if feature_tag != FEATURE_TAG_MARK && feature_tag != FEATURE_TAG_MKMK {
+            continue;
+        }
+        let feature = tables::subtable(feature_list, 0, record_offset + 4)?;
+        let lookup_index_count = tables::read_u16(feature, 2)?;
+        for lookup_index_index in 0..lookup_index_count as usize {
+            lookup_indices.push(tables::read_u16(feature, 4 + lookup_index_index * 2)?);
+        }
+    }
+    lookup_indices.sort();
+    lookup_indices.dedup();
+    Some(lookup_indices)
+}
+
+// Reads a `MarkBasePos` or `MarkMarkPos` subtable, which share a layout. Returns `Some(None)` if
+// the subtable doesn't cover both glyphs, or `None` if it's malformed.
+fn attachment_offset(
+    subtable: &[u8],
+    base_glyph_id: u16,
+    mark_glyph_id: u16,
+) -> Option<Option<Vector2F>> {
+    if tables::read_u16(subtable, 0)? != 1 {
+        return None;
+    }
+    let mark_coverage = tables::subtable(subtable, 0, 2)?;
+    let base_coverage = tables::subtable(subtable, 0, 4)?;
+    let (mark_index, base_index) = match (
+        layout::coverage_index(mark_coverage, mark_glyph_id)?,
+        layout::coverage_index(base_coverage, base_glyph_id)?,
+    ) {
+        (Some(mark_index), Some(base_index)) => (mark_index as usize, base_index as usize),
+        _ => return Some(None),
+    };
+    let mark_class_count = tables::read_u16(subtable, 6)? as usize;
+
+    let mark_array = tables::subtable(subtable, 0, 8)?;
+    if mark_index >= tables::read_u16(mark_array, 0)? as usize {
+        return None;
+    }
+    let mark_record_offset = 2 + mark_index * 4;
+    let mark_class = tables::read_u16(mark_array, mark_record_offset)? as usize;
+    let mark_anchor = anchor(tables::subtable(mark_array, 0, mark_record_offset + 2)?)?;
+    if mark_class >= mark_class_count {
+        return None;
+    }
+
+    let base_array = tables::subtable(subtable, 0, 10)?;
+    if base_index >= tables::read_u16(base_array, 0)? as usize {
+        return None;
+    }
+    // A null offset means the base has no anchor for marks of this class.
+    let base_anchor_offset = 2 + (base_index * mark_class_count + mark_class) * 2;
+    let base_anchor = match tables::read_u16(base_array, base_anchor_offset)? {
+        0 => return Some(None),
+        offset => anchor(base_array.get(offset as usize..)?)?,
+    };
+    Some(Some(base_anchor - mark_anchor))
+}
+
+// Reads the coordinates of an anchor table. Hinting adjustments are ignored.
+fn anchor(anchor: &[u8]) -> Option<Vector2F> {
+    match tables::read_u16(anchor, 0)? {
+        1..=3 => Some(Vector2F::new(
+            tables::read_i16(anchor, 2)? as f32,
+            tables::read_i16(anchor, 4)? as f32,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::mark_attachment_offset;
+    use pathfinder_geometry::vector::Vector2F;
+
+    #[rustfmt::skip]
+    const TABLE: [u8; 88] = [
+        0x00, 0x01, 0x00, 0x00, // version 1.0
+        0x00, 0x00,             // scriptListOffset
+        0x00, 0x0a,             // featureListOffset
+        0x00, 0x18,             // lookupListOffset
+        // FeatureList at 10
+        0x00, 0x01,             // featureCount
+        b'm', b'a', b'r', b'k', 0x00, 0x08,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, // lookup 0
+        // LookupList at 24
+        0x00, 0x01, 0x00, 0x04, // lookupCount, lookupOffsets[0]
+        0x00, 0x04, 0x00, 0x00, // lookupType: MarkBasePos, lookupFlag
+        0x00, 0x01, 0x00, 0x08, // subTableCount, subtableOffsets[0]
+        // MarkBasePos at 36
+        0x00, 0x01,             // format
+        0x00, 0x0c, 0x00, 0x12, // markCoverageOffset, baseCoverageOffset
+        0x00, 0x01,             // markClassCount
+        0x00, 0x1a, 0x00, 0x26, // markArrayOffset, baseArrayOffset
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x0a,             // mark coverage: glyph 10
+        0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, // base coverage: glyphs 3-4
+        // MarkArray at 62
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // markCount, class 0
+        0x00, 0x01, 0x00, 0x32, 0xff, 0xf6, // anchor (50, -10)
+        // BaseArray at 74
+        0x00, 0x02, 0x00, 0x06, 0x00, 0x00, // baseCount; glyph 4 has no anchor
+        0x00, 0x02, 0x01, 0x2c, 0x01, 0xf4, 0x00, 0x03, // anchor (300, 500), point 3
+    ];
+
+    #[test]
+    fn test_mark_attachment_offset() {
+        assert_eq!(
+            mark_attachment_offset(&TABLE, 3, 10),
+            Some(Vector2F::new(250.0, 510.0))
+        );
+        assert_eq!(mark_attachment_offset(&TABLE, 4, 10), None);
+        assert_eq!(mark_attachment_offset(&TABLE, 5, 10), None);
+        assert_eq!(mark_attachment_offset(&TABLE, 3, 11), None);
+        assert_eq!(mark_attachment_offset(&TABLE[..80], 3, 10), None);
+
+        // Lookups of other features are ignored.
+        let mut table = TABLE;
+        table[12..16].copy_from_slice(b"kern");
+        assert_eq!(mark_attachment_offset(&table, 3, 10), None);
+    }
+}
@@ -0,0 +1,191 @@
+// font-kit/src/tables/layout.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Structures shared by the OpenType layout tables (`GSUB`, `GPOS`, and `GDEF`).
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/chapter2
+
+use crate::tables;
+
+/// Returns the tags of all the features in the `FeatureList` of a `GSUB` or `GPOS` table.
+///
+/// Tags may be repeated, since a font may define the same feature separately for each script and
+/// language system.
+pub(crate) fn feature_tags(table: &[u8]) -> Option<Vec<u32>> {
+    let feature_list = tables::subtable(table, 0, 6)?;
+    let feature_count = tables::read_u16(feature_list, 0)?;
+    (0..feature_count as usize)
+        .map(|feature_index| tables::read_u32(feature_list, 2 + feature_index * 6))
+        .collect()
+}
+
+/// Returns the class of a glyph in a class definition table, or `None` if the table is malformed.
+///
+/// Glyphs not listed in the table are in class 0.
+pub(crate) fn glyph_class(class_def: &[u8], glyph_id: u16) -> Option<u16> {
+    match tables::read_u16(class_def, 0)? {
+        1 => {
+            let start_glyph_id = tables::read_u16(class_def, 2)?;
+            let glyph_count = tables::read_u16(class_def, 4)?;
+            match glyph_id.checked_sub(start_glyph_id) {
+                Some(index) if index < glyph_count => {
+                    tables::read_u16(class_def, 6 + index as usize * 2)
+                }
+                _ => Some(0),
+            }
+        }
+        2 => {
+            let range_count = tables::read_u16(class_def, 2)?;
+            match find_range(class_def, 4, range_count, glyph_id)? {
+                Some(record_offset) => tables::read_u16(class_def, record_offset + 4),
+                None => Some(0),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the index of a glyph in a coverage table, `Some(None)` if the glyph isn't covered, or
+/// `None` if the table is malformed.
+pub(crate) fn coverage_index(coverage: &[u8], glyph_id: u16) -> Option<Option<u16>> {
+    match tables::read_u16(coverage, 0)? {
+        1 => {
+            let glyph_count = tables::read_u16(coverage, 2)?;
+            let (mut low, mut high) = (0, glyph_count);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                let covered_glyph_id = tables::read_u16(coverage, 4 + mid as usize * 2)?;
+                if covered_glyph_id < glyph_id {
+                    low = mid + 1;
+                } else if covered_glyph_id > glyph_id {
+                    high = mid;
+                } else {
+                    return Some(Some(mid));
+                }
+            }
+            Some(None)
+        }
+        2 => {
+            let range_count = tables::read_u16(coverage, 2)?;
+            match find_range(coverage, 4, range_count, glyph_id)? {
+                Some(record_offset) => {
+                    let start_glyph_id = tables::read_u16(coverage, record_offset)?;
+                    let start_coverage_index = tables::read_u16(coverage, record_offset + 4)?;
+                    Some(start_coverage_index.checked_add(glyph_id - start_glyph_id))
+                }
+                None => Some(None),
+            }
+        }
+        _ => None,
+    }
+}
+
+// Binary searches the sorted `{ startGlyphID, endGlyphID, value }` records shared by class
+// definition and coverage tables, returning the offset of the record containing the glyph.
+fn find_range(
+    data: &[u8],
+    records_offset: usize,
+    range_count: u16,
+    glyph_id: u16,
+) -> Option<Option<usize>> {
+    let (mut low, mut high) = (0, range_count as usize);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let record_offset = records_offset + mid * 6;
+        let start_glyph_id = tables::read_u16(data, record_offset)?;
+        let end_glyph_id = tables::read_u16(data, record_offset + 2)?;
+        if end_glyph_id < glyph_id {
+            low = mid + 1;
+        } else if start_glyph_id > glyph_id {
+            high = mid;
+        } else {
+            return Some(Some(record_offset));
+        }
+    }
+    Some(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{coverage_index, feature_tags, glyph_class};
+
+    #[test]
+    fn test_feature_tags() {
+        #[rustfmt::skip]
+        let table = [
+            0x00, 0x01, 0x00, 0x00, // version 1.0
+            0x00, 0x00,             // scriptListOffset
+            0x00, 0x0a,             // featureListOffset
+            0x00, 0x00,             // lookupListOffset
+            0x00, 0x02,             // featureCount
+            b's', b'm', b'c', b'p', 0x00, 0x00,
+            b'o', b'n', b'u', b'm', 0x00, 0x00,
+        ];
+        assert_eq!(feature_tags(&table), Some(vec![0x736d6370, 0x6f6e756d]));
+        assert_eq!(feature_tags(&table[..14]), None);
+    }
+
+    #[test]
+    fn test_glyph_class() {
+        #[rustfmt::skip]
+        let format_1 = [
+            0x00, 0x01, // format
+            0x00, 0x0a, // startGlyphID
+            0x00, 0x02, // glyphCount
+            0x00, 0x03,
+            0x00, 0x01,
+        ];
+        assert_eq!(glyph_class(&format_1, 9), Some(0));
+        assert_eq!(glyph_class(&format_1, 10), Some(3));
+        assert_eq!(glyph_class(&format_1, 11), Some(1));
+        assert_eq!(glyph_class(&format_1, 12), Some(0));
+        assert_eq!(glyph_class(&format_1[..8], 11), None);
+
+        #[rustfmt::skip]
+        let format_2 = [
+            0x00, 0x02, // format
+            0x00, 0x02, // classRangeCount
+            0x00, 0x05, 0x00, 0x07, 0x00, 0x02,
+            0x00, 0x10, 0x00, 0x10, 0x00, 0x03,
+        ];
+        assert_eq!(glyph_class(&format_2, 4), Some(0));
+        assert_eq!(glyph_class(&format_2, 6), Some(2));
+        assert_eq!(glyph_class(&format_2, 8), Some(0));
+        assert_eq!(glyph_class(&format_2, 16), Some(3));
+        assert_eq!(glyph_class(&[0x00, 0x03], 16), None);
+    }
+
+    #[test]
+    fn test_coverage_index() {
+        #[rustfmt::skip]
+        let format_1 = [
+            0x00, 0x01, // format
+            0x00, 0x03, // glyphCount
+            0x00, 0x02,
+            0x00, 0x04,
+            0x00, 0x09,
+        ];
+        assert_eq!(coverage_index(&format_1, 4), Some(Some(1)));
+        assert_eq!(coverage_index(&format_1, 9), Some(Some(2)));
+        assert_eq!(coverage_index(&format_1, 5), Some(None));
+
+        #[rustfmt::skip]
+        let format_2 = [
+            0x00, 0x02, // format
+            0x00, 0x02, // rangeCount
+            0x00, 0x05, 0x00, 0x07, 0x00, 0x00,
+            0x00, 0x10, 0x00, 0x11, 0x00, 0x03,
+        ];
+        assert_eq!(coverage_index(&format_2, 6), Some(Some(1)));
+        assert_eq!(coverage_index(&format_2, 17), Some(Some(4)));
+        assert_eq!(coverage_index(&format_2, 8), Some(None));
+        assert_eq!(coverage_index(&format_2[..12], 17), None);
+    }
+}
@@ -0,0 +1,63 @@
+// font-kit/src/tables/loca.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The index to location table (`loca`), which locates the outlines of TrueType glyphs in the
+//! `glyf` table.
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/loca
+
+use crate::tables;
+
+// In the `head` table.
+const INDEX_TO_LOC_FORMAT_OFFSET: usize = 50;
+
+/// Returns true if the glyph with the given ID has no outline data, as is the case for spaces.
+///
+/// `head_table` is the font's `head` table, which determines the format of `loca_table`.
+pub(crate) fn glyph_is_empty(head_table: &[u8], loca_table: &[u8], glyph_id: u16) -> Option<bool> {
+    let glyph_index = glyph_id as usize;
+    let (start, end) = match tables::read_i16(head_table, INDEX_TO_LOC_FORMAT_OFFSET)? {
+        // Short offsets are stored divided by two.
+        0 => (
+            tables::read_u16(loca_table, glyph_index * 2)? as u32,
+            tables::read_u16(loca_table, glyph_index * 2 + 2)? as u32,
+        ),
+        1 => (
+            tables::read_u32(loca_table, glyph_index * 4)?,
+            tables::read_u32(loca_table, glyph_index * 4 + 4)?,
+        ),
+        _ => return None,
+    };
+    Some(end <= start)
+}
+
+#[cfg(test)]
+mod test {
+    use super::glyph_is_empty;
+
+    fn head_table(index_to_loc_format: u8) -> Vec<u8> {
+        let mut head_table = vec![0; 54];
+        head_table[51] = index_to_loc_format;
+        head_table
+    }
+
+    #[test]
+    fn test_glyph_is_empty() {
+        let loca_table = [0x00, 0x00, 0x00, 0x00, 0x00, 0x18];
+        assert_eq!(glyph_is_empty(&head_table(0), &loca_table, 0), Some(true));
+        assert_eq!(glyph_is_empty(&head_table(0), &loca_table, 1), Some(false));
+        assert_eq!(glyph_is_empty(&head_table(0), &loca_table, 2), None);
+
+        let loca_table = [0, 0, 0, 0, 0, 0, 0, 0x30, 0, 0, 0, 0x30];
+        assert_eq!(glyph_is_empty(&head_table(1), &loca_table, 0), Some(false));
+        assert_eq!(glyph_is_empty(&head_table(1), &loca_table, 1), Some(true));
+        assert_eq!(glyph_is_empty(&head_table(2), &loca_table, 0), None);
+    }
+}
@@ -0,0 +1,91 @@
+// font-kit/src/tables/math.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The mathematical typesetting table (`MATH`).
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/math
+
+use crate::tables::{self, layout};
+
+const MATH_GLYPH_INFO_OFFSET: usize = 6;
+const MATH_ITALICS_CORRECTION_INFO_OFFSET: usize = 0;
+const MATH_TOP_ACCENT_ATTACHMENT_OFFSET: usize = 2;
+
+// A `MathValueRecord` is a value followed by a device table offset, which we ignore.
+const MATH_VALUE_RECORD_SIZE: usize = 4;
+
+/// Returns the italics correction of a glyph in font units, or `None` if it has none.
+pub(crate) fn italics_correction(table: &[u8], glyph_id: u16) -> Option<i16> {
+    glyph_value(table, MATH_ITALICS_CORRECTION_INFO_OFFSET, glyph_id)
+}
+
+/// Returns the horizontal position at which accents attach above a glyph in font units, or `None`
+/// if the font doesn't specify one.
+pub(crate) fn top_accent_attachment(table: &[u8], glyph_id: u16) -> Option<i16> {
+    glyph_value(table, MATH_TOP_ACCENT_ATTACHMENT_OFFSET, glyph_id)
+}
+
+// Looks up a glyph in a `MathGlyphInfo` subtable of the common format: a coverage table followed
+// by a count and an array of `MathValueRecord`s indexed by coverage index.
+fn glyph_value(table: &[u8], info_offset: usize, glyph_id: u16) -> Option<i16> {
+    let math_glyph_info = tables::subtable(table, 0, MATH_GLYPH_INFO_OFFSET)?;
+    let info = tables::subtable(math_glyph_info, 0, info_offset)?;
+    let coverage = tables::subtable(info, 0, 0)?;
+    let coverage_index = layout::coverage_index(coverage, glyph_id)??;
+    if coverage_index >= tables::read_u16(info, 2)? {
+        return None;
+    }
+    tables::read_i16(info, 4 + coverage_index as usize * MATH_VALUE_RECORD_SIZE)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{italics_correction, top_accent_attachment};
+
+    #[rustfmt::skip]
+    const TABLE: [u8; 52] = [
+        0x00, 0x01, 0x00, 0x00, // version 1.0
+        0x00, 0x00,             // mathConstantsOffset
+        0x00, 0x0a,             // mathGlyphInfoOffset
+        0x00, 0x00,             // mathVariantsOffset
+        // MathGlyphInfo at 10
+        0x00, 0x08,             // mathItalicsCorrectionInfoOffset
+        0x00, 0x1c,             // mathTopAccentAttachmentOffset
+        0x00, 0x00,             // extendedShapeCoverageOffset
+        0x00, 0x00,             // mathKernInfoOffset
+        // MathItalicsCorrectionInfo at 18
+        0x00, 0x0c,             // italicsCorrectionCoverageOffset
+        0x00, 0x02,             // italicsCorrectionCount
+        0x00, 0x32, 0x00, 0x00, // italicsCorrection[0]: 50
+        0xff, 0xf6, 0x00, 0x00, // italicsCorrection[1]: -10
+        // Coverage at 30
+        0x00, 0x01, 0x00, 0x02, 0x00, 0x05, 0x00, 0x09,
+        // MathTopAccentAttachment at 38
+        0x00, 0x08,             // topAccentCoverageOffset
+        0x00, 0x01,             // topAccentAttachmentCount
+        0x01, 0x2c, 0x00, 0x00, // topAccentAttachment[0]: 300
+        // Coverage at 46
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x09,
+    ];
+
+    #[test]
+    fn test_italics_correction() {
+        assert_eq!(italics_correction(&TABLE, 5), Some(50));
+        assert_eq!(italics_correction(&TABLE, 9), Some(-10));
+        assert_eq!(italics_correction(&TABLE, 6), None);
+        assert_eq!(italics_correction(&TABLE[..36], 9), None);
+    }
+
+    #[test]
+    fn test_top_accent_attachment() {
+        assert_eq!(top_accent_attachment(&TABLE, 9), Some(300));
+        assert_eq!(top_accent_attachment(&TABLE, 5), None);
+    }
+}
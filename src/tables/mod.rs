@@ -0,0 +1,67 @@
+// font-kit/src/tables/mod.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Minimal readers for the OpenType tables that `font-kit` inspects itself.
+//!
+//! These operate on the raw table data returned by `Loader::load_font_table()`, so they work the
+//! same way regardless of which loader is in use. Malformed data is never a panic; the readers
+//! simply return `None`.
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+pub(crate) mod colr;
+pub(crate) mod gdef;
+pub(crate) mod gpos;
+pub(crate) mod layout;
+pub(crate) mod loca;
+pub(crate) mod math;
+pub(crate) mod os2;
+pub(crate) mod post;
+// Only Core Text draws `sbix` images.
+#[cfg_attr(not(any(target_os = "macos", target_os = "ios")), allow(dead_code))]
+pub(crate) mod sbix;
+
+pub(crate) const TABLE_TAG_COLR: u32 = 0x434f4c52; // 'COLR'
+pub(crate) const TABLE_TAG_GDEF: u32 = 0x47444546; // 'GDEF'
+pub(crate) const TABLE_TAG_GPOS: u32 = 0x47504f53; // 'GPOS'
+pub(crate) const TABLE_TAG_GSUB: u32 = 0x47535542; // 'GSUB'
+pub(crate) const TABLE_TAG_HEAD: u32 = 0x68656164; // 'head'
+pub(crate) const TABLE_TAG_LOCA: u32 = 0x6c6f6361; // 'loca'
+pub(crate) const TABLE_TAG_MATH: u32 = 0x4d415448; // 'MATH'
+pub(crate) const TABLE_TAG_OS2: u32 = 0x4f532f32; // 'OS/2'
+pub(crate) const TABLE_TAG_POST: u32 = 0x706f7374; // 'post'
+#[cfg_attr(not(any(target_os = "macos", target_os = "ios")), allow(dead_code))]
+pub(crate) const TABLE_TAG_SBIX: u32 = 0x73626978; // 'sbix'
+
+#[inline]
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..)?.read_u16::<BigEndian>().ok()
+}
+
+#[inline]
+pub(crate) fn read_i16(data: &[u8], offset: usize) -> Option<i16> {
+    data.get(offset..)?.read_i16::<BigEndian>().ok()
+}
+
+#[inline]
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..)?.read_u32::<BigEndian>().ok()
+}
+
+/// Follows a 16-bit offset stored at `offset` to a subtable that starts relative to `base`.
+///
+/// Null offsets yield `None`, as they do everywhere in OpenType.
+#[inline]
+pub(crate) fn subtable(data: &[u8], base: usize, offset: usize) -> Option<&[u8]> {
+    match read_u16(data, offset)? {
+        0 => None,
+        subtable_offset => data.get((base + subtable_offset as usize)..),
+    }
+}
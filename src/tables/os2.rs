@@ -0,0 +1,52 @@
+// font-kit/src/tables/os2.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The OS/2 and Windows metrics table (`OS/2`).
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/os2
+
+use std::convert::TryInto;
+
+use crate::classification::{Classification, FamilyClass, Panose};
+
+const FAMILY_CLASS_OFFSET: usize = 30;
+const PANOSE_OFFSET: usize = 32;
+const PANOSE_LENGTH: usize = 10;
+
+/// Returns the family class and PANOSE numbers of the face.
+pub(crate) fn classification(table: &[u8]) -> Option<Classification> {
+    // `sFamilyClass` packs the class ID into the high byte and the subclass ID into the low byte.
+    let family_class = table.get(FAMILY_CLASS_OFFSET..PANOSE_OFFSET)?;
+    let panose = table.get(PANOSE_OFFSET..(PANOSE_OFFSET + PANOSE_LENGTH))?;
+    Some(Classification {
+        family_class: FamilyClass::from_id(family_class[0]),
+        family_subclass: family_class[1],
+        panose: Panose(panose.try_into().ok()?),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::classification;
+    use crate::classification::{FamilyClass, Panose};
+
+    #[test]
+    fn test_classification() {
+        let mut table = vec![0; 78];
+        table[30] = 8;
+        table[31] = 1;
+        table[32..42].copy_from_slice(&[2, 11, 6, 4, 2, 2, 2, 2, 2, 4]);
+        let sans_serif = classification(&table).unwrap();
+        assert_eq!(sans_serif.family_class, FamilyClass::SansSerif);
+        assert_eq!(sans_serif.family_subclass, 1);
+        assert_eq!(sans_serif.panose, Panose([2, 11, 6, 4, 2, 2, 2, 2, 2, 4]));
+        assert_eq!(classification(&table[..40]), None);
+    }
+}
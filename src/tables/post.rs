@@ -0,0 +1,42 @@
+// font-kit/src/tables/post.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The PostScript table (`post`).
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/post
+
+use crate::tables;
+
+const ITALIC_ANGLE_OFFSET: usize = 4;
+
+/// Returns the italic angle in degrees counterclockwise from the vertical.
+pub(crate) fn italic_angle(table: &[u8]) -> Option<f32> {
+    // The angle is a 16.16 fixed-point number.
+    let italic_angle = tables::read_u32(table, ITALIC_ANGLE_OFFSET)? as i32;
+    Some(italic_angle as f32 / 65536.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::italic_angle;
+
+    #[test]
+    fn test_italic_angle() {
+        assert_eq!(
+            italic_angle(&[0, 3, 0, 0, 0xff, 0xef, 0x00, 0x00]),
+            Some(-17.0)
+        );
+        assert_eq!(
+            italic_angle(&[0, 3, 0, 0, 0x00, 0x00, 0x80, 0x00]),
+            Some(0.5)
+        );
+        assert_eq!(italic_angle(&[0, 3, 0, 0]), None);
+    }
+}
@@ -0,0 +1,85 @@
+// font-kit/src/tables/sbix.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The standard bitmap graphics table (`sbix`).
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/sbix
+
+use crate::tables;
+
+const SBIX_NUM_STRIKES_OFFSET: usize = 4;
+const SBIX_STRIKE_OFFSETS_OFFSET: usize = 8;
+const SBIX_STRIKE_GLYPH_DATA_OFFSETS_OFFSET: usize = 4;
+
+/// Returns the sizes, in pixels per em, of the strikes in the `sbix` table that have an image for
+/// the given glyph.
+pub(crate) fn strikes(table: &[u8], glyph_id: u16) -> Option<Vec<u16>> {
+    let num_strikes = tables::read_u32(table, SBIX_NUM_STRIKES_OFFSET)? as usize;
+    let mut strikes = vec![];
+    for strike_index in 0..num_strikes {
+        let strike_offset =
+            tables::read_u32(table, SBIX_STRIKE_OFFSETS_OFFSET + strike_index * 4)? as usize;
+        let strike = table.get(strike_offset..)?;
+        let offset = SBIX_STRIKE_GLYPH_DATA_OFFSETS_OFFSET + glyph_id as usize * 4;
+        if tables::read_u32(strike, offset + 4)? > tables::read_u32(strike, offset)? {
+            strikes.push(tables::read_u16(strike, 0)?);
+        }
+    }
+    Some(strikes)
+}
+
+/// Chooses the strike to draw at the given size: the smallest strike at least as large, so that
+/// the bitmap is scaled down, or else the largest strike.
+pub(crate) fn select_strike(strikes: &[u16], px_per_em: f32) -> Option<u16> {
+    strikes
+        .iter()
+        .cloned()
+        .filter(|&ppem| ppem as f32 >= px_per_em)
+        .min()
+        .or_else(|| strikes.iter().cloned().max())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{select_strike, strikes};
+
+    #[rustfmt::skip]
+    const SBIX_TABLE: [u8; 48] = [
+        0x00, 0x01, 0x00, 0x01, // version, flags
+        0x00, 0x00, 0x00, 0x02, // numStrikes
+        0x00, 0x00, 0x00, 0x10, // strikeOffsets[0]
+        0x00, 0x00, 0x00, 0x20, // strikeOffsets[1]
+        // Strike at 16
+        0x00, 0x20, 0x00, 0x48, // ppem: 32, ppi: 72
+        0x00, 0x00, 0x00, 0x10, // glyphDataOffsets[0]
+        0x00, 0x00, 0x00, 0x10, // glyphDataOffsets[1]
+        0x00, 0x00, 0x00, 0x20, // glyphDataOffsets[2]
+        // Strike at 32
+        0x00, 0x40, 0x00, 0x48, // ppem: 64, ppi: 72
+        0x00, 0x00, 0x00, 0x10, // glyphDataOffsets[0]
+        0x00, 0x00, 0x00, 0x20, // glyphDataOffsets[1]
+        0x00, 0x00, 0x00, 0x20, // glyphDataOffsets[2]
+    ];
+
+    #[test]
+    fn test_strikes() {
+        assert_eq!(strikes(&SBIX_TABLE, 0), Some(vec![64]));
+        assert_eq!(strikes(&SBIX_TABLE, 1), Some(vec![32]));
+        assert_eq!(strikes(&SBIX_TABLE[..40], 1), None);
+    }
+
+    #[test]
+    fn test_select_strike() {
+        assert_eq!(select_strike(&[20, 40, 109], 16.0), Some(20));
+        assert_eq!(select_strike(&[20, 40, 109], 40.0), Some(40));
+        assert_eq!(select_strike(&[20, 40, 109], 200.0), Some(109));
+        assert_eq!(select_strike(&[], 16.0), None);
+    }
+}
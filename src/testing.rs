@@ -0,0 +1,326 @@
+// font-kit/src/testing.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A conformance test battery for `Loader` implementations.
+//!
+//! Every loader is expected to behave identically on the bundled fixture fonts. Each `check_*`
+//! function here exercises one area of the `Loader` API and panics on a mismatch, so it can be
+//! called from any `#[test]`. The `loader_test_suite!` macro generates one test per check:
+//!
+//! ```ignore
+//! font_kit::loader_test_suite!(my_crate::MyLoader);
+//! ```
+//!
+//! This module is only available with the `testing` feature.
+
+use pathfinder_geometry::transform2d::Transform2F;
+use std::sync::Arc;
+
+use crate::canvas::RasterizationOptions;
+use crate::error::GlyphLoadingError;
+use crate::file_type::FileType;
+use crate::hinting::HintingOptions;
+use crate::loader::Loader;
+use crate::outline::{Outline, OutlineBuilder};
+use crate::properties::{Stretch, Style, Weight};
+
+/// EB Garamond 12 Regular, an OpenType font with CFF outlines.
+pub static EB_GARAMOND_OTF: &[u8] =
+    include_bytes!("../resources/tests/eb-garamond/EBGaramond12-Regular.otf");
+/// An OpenType collection containing EB Garamond 12 Regular and Italic, in that order.
+pub static EB_GARAMOND_OTC: &[u8] =
+    include_bytes!("../resources/tests/eb-garamond/EBGaramond12.otc");
+/// Inconsolata Regular, a monospace TrueType font.
+pub static INCONSOLATA_TTF: &[u8] =
+    include_bytes!("../resources/tests/inconsolata/Inconsolata-Regular.ttf");
+
+/// Data that is not a font in any format.
+pub static NOT_A_FONT: &[u8] = b"This is not a font file, but it is long enough to look like one.";
+
+const OPENTYPE_TABLE_TAG_HEAD: u32 = 0x68656164;
+
+const SFNT_VERSIONS: [[u8; 4]; 3] = [
+    [0x00, 0x01, 0x00, 0x00],
+    [b'O', b'T', b'T', b'O'],
+    [b't', b't', b'c', b'f'],
+];
+
+/// Loads the font at `font_index` in a fixture with the given loader, panicking on failure.
+pub fn load_fixture<L>(data: &[u8], font_index: u32) -> L
+where
+    L: Loader,
+{
+    match L::from_bytes(Arc::new(data.to_vec()), font_index) {
+        Ok(font) => font,
+        Err(error) => panic!("failed to load fixture font {}: {:?}", font_index, error),
+    }
+}
+
+/// Checks that fonts load from memory and report their PostScript names.
+pub fn check_load_from_bytes<L>()
+where
+    L: Loader,
+{
+    let font = load_fixture::<L>(EB_GARAMOND_OTF, 0);
+    assert_eq!(
+        font.postscript_name().as_deref(),
+        Some("EBGaramond12-Regular")
+    );
+    let font = load_fixture::<L>(INCONSOLATA_TTF, 0);
+    assert_eq!(
+        font.postscript_name().as_deref(),
+        Some("Inconsolata-Regular")
+    );
+}
+
+/// Checks that single fonts and collections are told apart without loading them.
+pub fn check_analyze_bytes<L>()
+where
+    L: Loader,
+{
+    assert_eq!(
+        L::analyze_bytes(Arc::new(EB_GARAMOND_OTF.to_vec())).unwrap(),
+        FileType::Single
+    );
+    assert_eq!(
+        L::analyze_bytes(Arc::new(INCONSOLATA_TTF.to_vec())).unwrap(),
+        FileType::Single
+    );
+    assert_eq!(
+        L::analyze_bytes(Arc::new(EB_GARAMOND_OTC.to_vec())).unwrap(),
+        FileType::Collection(2)
+    );
+}
+
+/// Checks that the family, full, and PostScript names come from the `name` table.
+pub fn check_names<L>()
+where
+    L: Loader,
+{
+    let font = load_fixture::<L>(EB_GARAMOND_OTF, 0);
+    assert_eq!(font.family_name(), "EB Garamond 12");
+    assert_eq!(font.full_name(), "EB Garamond 12 Regular");
+    let font = load_fixture::<L>(INCONSOLATA_TTF, 0);
+    assert_eq!(font.family_name(), "Inconsolata");
+    assert_eq!(font.full_name(), "Inconsolata Regular");
+}
+
+/// Checks style, weight, stretch, and monospace detection.
+pub fn check_properties<L>()
+where
+    L: Loader,
+{
+    let font = load_fixture::<L>(EB_GARAMOND_OTF, 0);
+    let properties = font.properties();
+    assert_eq!(properties.style, Style::Normal);
+    assert_eq!(properties.weight, Weight::NORMAL);
+    assert_eq!(properties.stretch, Stretch::NORMAL);
+    assert!(!font.is_monospace());
+
+    let font = load_fixture::<L>(EB_GARAMOND_OTC, 1);
+    assert_eq!(font.properties().style, Style::Italic);
+
+    let font = load_fixture::<L>(INCONSOLATA_TTF, 0);
+    assert!(font.is_monospace());
+}
+
+/// Checks the font-wide metrics that every backend should agree on.
+pub fn check_metrics<L>()
+where
+    L: Loader,
+{
+    for &data in &[EB_GARAMOND_OTF, INCONSOLATA_TTF] {
+        let metrics = load_fixture::<L>(data, 0).metrics();
+        assert_eq!(metrics.units_per_em, 1000);
+        assert!(metrics.ascent > 0.0);
+        assert!(metrics.descent < 0.0);
+        assert!(metrics.line_gap >= 0.0);
+        assert!(metrics.underline_thickness > 0.0);
+        assert!(metrics.bounding_box.width() > 0.0);
+        assert!(metrics.bounding_box.height() > 0.0);
+    }
+}
+
+/// Checks the character map and glyph count.
+pub fn check_glyphs<L>()
+where
+    L: Loader,
+{
+    let font = load_fixture::<L>(EB_GARAMOND_OTF, 0);
+    assert_eq!(font.glyph_count(), 3084);
+    assert!(font.glyph_for_char('a').is_some());
+    assert!(font.glyph_for_char('!').is_some());
+    // Loaders may map missing characters to `.notdef` rather than `None`.
+    assert!(!font.has_glyph_for_char('\u{4e00}'));
+
+    let font = load_fixture::<L>(INCONSOLATA_TTF, 0);
+    assert_eq!(font.glyph_count(), 799);
+}
+
+/// Checks advances and typographic bounds of ordinary glyphs.
+pub fn check_glyph_metrics<L>()
+where
+    L: Loader,
+{
+    let font = load_fixture::<L>(INCONSOLATA_TTF, 0);
+    let glyph_i = font.glyph_for_char('i').expect("No glyph for char!");
+    let glyph_m = font.glyph_for_char('m').expect("No glyph for char!");
+    let advance = font.advance(glyph_i).unwrap();
+    assert!(advance.x() > 0.0);
+    assert_eq!(advance.y(), 0.0);
+    assert_eq!(font.advance(glyph_m), Ok(advance));
+
+    let bounds = font.typographic_bounds(glyph_i).unwrap();
+    assert!(bounds.width() > 0.0);
+    assert!(bounds.height() > 0.0);
+}
+
+/// Checks that quadratic and cubic outlines are produced, and that blank glyphs have none.
+pub fn check_outlines<L>()
+where
+    L: Loader,
+{
+    for &data in &[EB_GARAMOND_OTF, INCONSOLATA_TTF] {
+        let font = load_fixture::<L>(data, 0);
+        let glyph = font.glyph_for_char('!').expect("No glyph for char!");
+        let mut outline_builder = OutlineBuilder::new();
+        font.outline(glyph, HintingOptions::None, &mut outline_builder)
+            .unwrap();
+        let outline = outline_builder.into_outline();
+        assert_eq!(outline.contours.len(), 2);
+        for contour in &outline.contours {
+            assert!(contour.positions.len() >= 3);
+            assert_eq!(contour.positions.len(), contour.flags.len());
+        }
+
+        let glyph = font.glyph_for_char(' ').expect("No glyph for char!");
+        let mut outline_builder = OutlineBuilder::new();
+        font.outline(glyph, HintingOptions::None, &mut outline_builder)
+            .unwrap();
+        assert_eq!(outline_builder.into_outline(), Outline::new());
+    }
+}
+
+/// Checks access to the raw font data and individual tables.
+pub fn check_font_data<L>()
+where
+    L: Loader,
+{
+    let font = load_fixture::<L>(EB_GARAMOND_OTF, 0);
+    let data = font.copy_font_data().expect("No font data!");
+    assert!(SFNT_VERSIONS.iter().any(|version| data[0..4] == *version));
+    let head_table = font
+        .load_font_table(OPENTYPE_TABLE_TAG_HEAD)
+        .expect("Where's the `head` table?");
+    assert_eq!(&head_table[12..16], &[0x5f, 0x0f, 0x3c, 0xf5]);
+    assert!(font.load_font_table(0x7a7a7a7a).is_none());
+}
+
+/// Checks that each member of a collection can be loaded by index.
+pub fn check_collections<L>()
+where
+    L: Loader,
+{
+    let regular = load_fixture::<L>(EB_GARAMOND_OTC, 0);
+    assert_eq!(
+        regular.postscript_name().as_deref(),
+        Some("EBGaramond12-Regular")
+    );
+    let italic = load_fixture::<L>(EB_GARAMOND_OTC, 1);
+    assert_eq!(
+        italic.postscript_name().as_deref(),
+        Some("EBGaramond12-Italic")
+    );
+    assert_eq!(italic.glyph_count(), 2516);
+}
+
+/// Checks that bad input is reported as an error rather than a panic.
+pub fn check_error_cases<L>()
+where
+    L: Loader,
+{
+    assert!(L::from_bytes(Arc::new(NOT_A_FONT.to_vec()), 0).is_err());
+    assert!(L::from_bytes(Arc::new(vec![]), 0).is_err());
+    assert!(L::analyze_bytes(Arc::new(NOT_A_FONT.to_vec())).is_err());
+    assert!(L::from_bytes(Arc::new(EB_GARAMOND_OTC.to_vec()), 2).is_err());
+
+    // Glyph IDs past the end of the font are rejected, not drawn as `.notdef`.
+    let font = load_fixture::<L>(INCONSOLATA_TTF, 0);
+    let glyph_id = font.glyph_count();
+    let mut outline_builder = OutlineBuilder::new();
+    assert_eq!(
+        font.outline(glyph_id, HintingOptions::None, &mut outline_builder),
+        Err(GlyphLoadingError::NoSuchGlyph)
+    );
+    assert_eq!(
+        font.typographic_bounds(glyph_id),
+        Err(GlyphLoadingError::NoSuchGlyph)
+    );
+    assert_eq!(font.advance(glyph_id), Err(GlyphLoadingError::NoSuchGlyph));
+    assert_eq!(
+        font.raster_bounds(
+            glyph_id,
+            32.0,
+            Transform2F::default(),
+            HintingOptions::None,
+            RasterizationOptions::GrayscaleAa,
+        ),
+        Err(GlyphLoadingError::NoSuchGlyph)
+    );
+}
+
+/// Generates a `#[test]` for every check in `font_kit::testing`, run against the given loader.
+///
+/// Invoke it once at module level in a test target, with the `testing` feature enabled:
+///
+/// ```ignore
+/// font_kit::loader_test_suite!(font_kit::loaders::freetype::Font);
+/// ```
+///
+/// To mark the checks that a loader is known to fail, list the checks explicitly and put
+/// attributes before them, so that they are still reported by `cargo test`:
+///
+/// ```ignore
+/// font_kit::loader_test_suite!(
+///     my_crate::MyLoader;
+///     check_load_from_bytes,
+///     #[ignore = "MyLoader: analysis is unimplemented"]
+///     check_analyze_bytes,
+///     // ...
+/// );
+/// ```
+#[macro_export]
+macro_rules! loader_test_suite {
+    ($loader:ty) => {
+        $crate::loader_test_suite!(
+            $loader;
+            check_load_from_bytes,
+            check_analyze_bytes,
+            check_names,
+            check_properties,
+            check_metrics,
+            check_glyphs,
+            check_glyph_metrics,
+            check_outlines,
+            check_font_data,
+            check_collections,
+            check_error_cases
+        );
+    };
+    ($loader:ty; $($(#[$attribute:meta])* $check:ident),+ $(,)?) => {
+        $(
+            #[test]
+            $(#[$attribute])*
+            fn $check() {
+                $crate::testing::$check::<$loader>();
+            }
+        )+
+    };
+}
@@ -0,0 +1,46 @@
+// font-kit/tests/loader_conformance.rs
+//
+// Copyright © 2024 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Runs the `font_kit::testing` conformance battery against the default loader.
+//
+// Run with `cargo test --features testing --test loader_conformance`.
+
+// Every check is run against every loader. The checks that the swash loader, used on other
+// platforms, is known to fail are ignored there, so that `cargo test` still lists them.
+font_kit::loader_test_suite!(
+    font_kit::font::Font;
+    check_load_from_bytes,
+    #[cfg_attr(
+        not(any(target_family = "windows", target_os = "macos", target_os = "ios")),
+        ignore = "swash: analyze_bytes is unimplemented"
+    )]
+    check_analyze_bytes,
+    check_names,
+    #[cfg_attr(
+        not(any(target_family = "windows", target_os = "macos", target_os = "ios")),
+        ignore = "swash: properties reports stretch as a percentage"
+    )]
+    check_properties,
+    check_metrics,
+    check_glyphs,
+    #[cfg_attr(
+        not(any(target_family = "windows", target_os = "macos", target_os = "ios")),
+        ignore = "swash: typographic_bounds and advance are unimplemented"
+    )]
+    check_glyph_metrics,
+    check_outlines,
+    check_font_data,
+    check_collections,
+    #[cfg_attr(
+        not(any(target_family = "windows", target_os = "macos", target_os = "ios")),
+        ignore = "swash: analyze_bytes is unimplemented"
+    )]
+    check_error_cases,
+);
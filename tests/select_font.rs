@@ -43,6 +43,7 @@ macro_rules! match_handle {
                     font_index, $index
                 );
             }
+            _ => panic!("Expected path or memory handle!"),
         }
     };
 }
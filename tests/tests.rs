@@ -10,22 +10,46 @@
 
 // General tests.
 
-use font_kit::canvas::{Canvas, Format, RasterizationOptions};
+use font_kit::canvas::{CancellationToken, Canvas, Format, GlyphRepresentation};
+use font_kit::canvas::{RasterizationBudget, RasterizationOptions, Synthesis};
+use font_kit::classification::{FamilyClass, SerifStyle};
+#[cfg(any(target_family = "windows", target_os = "macos"))]
+use font_kit::color::Paint;
+use font_kit::error::{GlyphLoadingError, SelectionError};
 use font_kit::family_name::FamilyName;
 use font_kit::file_type::FileType;
 use font_kit::font::Font;
+use font_kit::gamma::GammaLut;
+use font_kit::handle::Handle;
 use font_kit::hinting::HintingOptions;
+use font_kit::layout::{GlyphClass, LigatureCaret};
+use font_kit::loader::Loader;
+#[cfg(any(target_family = "windows", target_os = "macos"))]
+use font_kit::metrics::{RoundingMode, TextSize};
 use font_kit::outline::{Contour, Outline, OutlineBuilder, PointFlags};
-use font_kit::properties::{Properties, Stretch, Weight};
+use font_kit::properties::{Features, Properties, Requirements, Stretch, Style, Weight};
 use pathfinder_geometry::rect::{RectF, RectI};
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::{Vector2F, Vector2I};
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Read;
 use std::sync::Arc;
+use std::time::Instant;
 
 #[cfg(feature = "source")]
-use font_kit::source::SystemSource;
+use font_kit::classification::ClassificationQuery;
+#[cfg(feature = "source")]
+use font_kit::family_handle::FamilyHandle;
+#[cfg(feature = "source")]
+use font_kit::source::{Source, SystemSource};
+#[cfg(feature = "source")]
+use font_kit::sources::alias::AliasSource;
+#[cfg(feature = "source")]
+use font_kit::sources::mem::MemSource;
+#[cfg(feature = "source")]
+use font_kit::substitution::SubstitutionReason;
+#[cfg(feature = "source")]
+use std::any::Any;
 
 static TEST_FONT_FILE_PATH: &'static str = "resources/tests/eb-garamond/EBGaramond12-Regular.otf";
 static TEST_FONT_POSTSCRIPT_NAME: &'static str = "EBGaramond12-Regular";
@@ -540,6 +564,26 @@ pub fn get_glyph_advance_and_origin() {
     assert_eq!(font.origin(glyph), Ok(Vector2F::default()));
 }
 
+#[cfg(all(
+    feature = "source",
+    any(target_family = "windows", target_os = "macos")
+))]
+#[test]
+pub fn get_glyph_advance_scaled() {
+    let font = SystemSource::new()
+        .select_best_match(&[FamilyName::SansSerif], &Properties::new())
+        .unwrap()
+        .load()
+        .unwrap();
+    let glyph = font.glyph_for_char('a').expect("No glyph for char!");
+    let advance = font.advance_scaled(glyph, 12.0, 96.0, RoundingMode::None);
+    assert_eq!(advance, Ok(Vector2F::new(1139.0 / 128.0, 0.0)));
+    let advance = font.advance_scaled(glyph, 12.0, 96.0, RoundingMode::Nearest);
+    assert_eq!(advance, Ok(Vector2F::new(9.0, 0.0)));
+    let advance = font.advance_scaled(glyph, 12.0, 96.0, RoundingMode::Floor);
+    assert_eq!(advance, Ok(Vector2F::new(8.0, 0.0)));
+}
+
 #[cfg(all(
     feature = "source",
     not(any(target_family = "windows", target_os = "macos", target_os = "ios"))
@@ -651,6 +695,752 @@ pub fn load_font_table() {
     assert_eq!(&head_table[12..16], &[0x5f, 0x0f, 0x3c, 0xf5]);
 }
 
+#[test]
+pub fn get_supported_features() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    assert!(font.feature_tags().contains(&0x736d6370)); // 'smcp'
+    assert!(font
+        .supported_features()
+        .contains(Features::SMALL_CAPS | Features::OLDSTYLE_FIGURES));
+
+    let font = Font::from_path(FILE_PATH_INCONSOLATA_TTF, 0).unwrap();
+    assert!(!font.supported_features().contains(Features::SMALL_CAPS));
+}
+
+#[test]
+pub fn get_color_layers_of_non_color_font() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    assert!(font.color_layers(1).is_empty());
+}
+
+#[cfg(any(target_family = "windows", target_os = "macos"))]
+#[test]
+pub fn rasterize_color_layers_of_non_color_glyph() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    let glyph_id = font.glyph_for_char('A').unwrap();
+    let masks = font
+        .rasterize_color_layers(
+            glyph_id,
+            16.0,
+            Transform2F::default(),
+            HintingOptions::None,
+            RasterizationOptions::GrayscaleAa,
+        )
+        .unwrap();
+    assert_eq!(masks.len(), 1);
+    assert_eq!(masks[0].glyph_id, glyph_id);
+    assert_eq!(masks[0].paint, Paint::Foreground);
+    assert_eq!(masks[0].canvas.format, Format::A8);
+    assert_eq!(masks[0].canvas.size, masks[0].bounds.size());
+    assert!(masks[0].canvas.pixels.iter().any(|&value| value > 0));
+}
+
+#[cfg(any(target_family = "windows", target_os = "macos"))]
+#[test]
+pub fn rasterize_glyph_for_text_size() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    let glyph_id = font.glyph_for_char('a').unwrap();
+    let size = TextSize::new(12.0, 144.0);
+    let transform = Transform2F::default();
+    let bounds = font
+        .raster_bounds_for_size(
+            glyph_id,
+            size,
+            transform,
+            HintingOptions::None,
+            RasterizationOptions::Deterministic,
+        )
+        .unwrap();
+    assert_eq!(
+        bounds,
+        font.raster_bounds(
+            glyph_id,
+            24.0,
+            transform,
+            HintingOptions::None,
+            RasterizationOptions::Deterministic,
+        )
+        .unwrap()
+    );
+
+    let mut canvas = Canvas::new(bounds.size(), Format::A8);
+    font.rasterize_glyph_for_size(
+        &mut canvas,
+        glyph_id,
+        size,
+        Transform2F::from_translation(-bounds.origin().to_f32()) * transform,
+        HintingOptions::None,
+        RasterizationOptions::Deterministic,
+    )
+    .unwrap();
+    assert!(canvas.pixels.iter().any(|&value| value > 0));
+
+    assert_eq!(font.scaled_metrics(size), font.metrics().to_device(24.0));
+    assert_eq!(
+        font.advance_for_size(glyph_id, size, RoundingMode::None)
+            .unwrap(),
+        font.advance_scaled(glyph_id, 12.0, 144.0, RoundingMode::None)
+            .unwrap()
+    );
+}
+
+#[test]
+pub fn get_notdef_is_blank() {
+    let font = Font::from_path(FILE_PATH_INCONSOLATA_TTF, 0).unwrap();
+    assert_eq!(font.notdef_is_blank(), Ok(true));
+    let font = Font::from_path(FILE_PATH_EB_GARAMOND_TTF, 0).unwrap();
+    assert_eq!(font.notdef_is_blank(), Ok(false));
+}
+
+#[test]
+pub fn get_notdef_is_blank_of_cff_font() {
+    // CFF fonts have no `loca` table, so the outline of `.notdef` is loaded.
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    assert_eq!(font.notdef_is_blank(), Ok(false));
+    assert!(font.has_glyph_for_char('A'));
+    assert!(!font.has_glyph_for_char('\u{4e00}'));
+}
+
+#[cfg(any(target_family = "windows", target_os = "macos"))]
+#[test]
+pub fn rasterize_notdef() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    let bounds = font
+        .notdef_raster_bounds(
+            16.0,
+            Transform2F::default(),
+            HintingOptions::None,
+            RasterizationOptions::GrayscaleAa,
+        )
+        .unwrap();
+    let mut canvas = Canvas::new(bounds.size(), Format::A8);
+    font.rasterize_notdef(
+        &mut canvas,
+        16.0,
+        Transform2F::from_translation(-bounds.origin().to_f32()),
+        HintingOptions::None,
+        RasterizationOptions::GrayscaleAa,
+    )
+    .unwrap();
+    assert!(canvas.pixels.iter().any(|&value| value > 0));
+}
+
+#[test]
+pub fn get_glyph_raster_info() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    let (bounds, info) = font
+        .raster_bounds_with_info(
+            1,
+            16.0,
+            Transform2F::default(),
+            HintingOptions::None,
+            RasterizationOptions::Deterministic,
+        )
+        .unwrap();
+    assert_eq!(info.representation, GlyphRepresentation::Outline);
+    assert_eq!(info.synthesis, Synthesis::default());
+
+    let mut canvas = Canvas::new(bounds.size(), Format::A8);
+    let info = font
+        .rasterize_glyph_with_info(
+            &mut canvas,
+            1,
+            16.0,
+            Transform2F::from_translation(-bounds.origin().to_f32()),
+            HintingOptions::None,
+            RasterizationOptions::Deterministic,
+        )
+        .unwrap();
+    assert_eq!(info.representation, GlyphRepresentation::Outline);
+    assert_eq!(info.synthesis, Synthesis::default());
+}
+
+#[test]
+pub fn get_color_glyph_raster_info() {
+    // A copy of Inconsolata in which 'E' is a color glyph with two layers.
+    #[rustfmt::skip]
+    let colr_table = vec![
+        0x00, 0x00,             // version
+        0x00, 0x01,             // numBaseGlyphRecords
+        0x00, 0x00, 0x00, 0x0e, // baseGlyphRecordsOffset
+        0x00, 0x00, 0x00, 0x14, // layerRecordsOffset
+        0x00, 0x02,             // numLayerRecords
+        0x00, 0x2d, 0x00, 0x00, 0x00, 0x02, // glyph 45: layers 0-1
+        0x00, 0x2d, 0x00, 0x00, // glyph 45, palette index 0
+        0x00, 0x2e, 0xff, 0xff, // glyph 46, foreground
+    ];
+    #[rustfmt::skip]
+    let cpal_table = vec![
+        0x00, 0x00,             // version
+        0x00, 0x01,             // numPaletteEntries
+        0x00, 0x01,             // numPalettes
+        0x00, 0x01,             // numColorRecords
+        0x00, 0x00, 0x00, 0x0e, // colorRecordsArrayOffset
+        0x00, 0x00,             // colorRecordIndices[0]
+        0x00, 0x00, 0xff, 0xff, // red, as BGRA
+    ];
+    let font_data = add_font_tables(
+        &fs::read(FILE_PATH_INCONSOLATA_TTF).unwrap(),
+        vec![(0x434f4c52, colr_table), (0x4350414c, cpal_table)],
+    );
+    let font = Font::from_bytes(Arc::new(font_data), 0).unwrap();
+    assert_eq!(font.color_layers(45).len(), 2);
+
+    // Only Core Text composites the layers; DirectWrite draws the base glyph's outline. The swash
+    // loader only rasterizes deterministically.
+    #[cfg(any(target_os = "macos", target_family = "windows"))]
+    {
+        let expected = if cfg!(target_os = "macos") {
+            GlyphRepresentation::ColorLayers
+        } else {
+            GlyphRepresentation::Outline
+        };
+        let (_, info) = font
+            .raster_bounds_with_info(
+                45,
+                16.0,
+                Transform2F::default(),
+                HintingOptions::None,
+                RasterizationOptions::GrayscaleAa,
+            )
+            .unwrap();
+        assert_eq!(info.representation, expected);
+        let (_, info) = font
+            .raster_bounds_with_info(
+                46,
+                16.0,
+                Transform2F::default(),
+                HintingOptions::None,
+                RasterizationOptions::GrayscaleAa,
+            )
+            .unwrap();
+        assert_eq!(info.representation, GlyphRepresentation::Outline);
+    }
+
+    // The deterministic rasterizer never composites layers.
+    let bounds = font
+        .raster_bounds(
+            45,
+            16.0,
+            Transform2F::default(),
+            HintingOptions::None,
+            RasterizationOptions::Deterministic,
+        )
+        .unwrap();
+    let mut canvas = Canvas::new(bounds.size(), Format::A8);
+    let info = font
+        .rasterize_glyph_with_info(
+            &mut canvas,
+            45,
+            16.0,
+            Transform2F::from_translation(-bounds.origin().to_f32()),
+            HintingOptions::None,
+            RasterizationOptions::Deterministic,
+        )
+        .unwrap();
+    assert_eq!(info.representation, GlyphRepresentation::Outline);
+}
+
+#[test]
+pub fn apply_gamma_correction() {
+    let gamma_lut = GammaLut::new(0.0, 2.2);
+
+    let mut canvas = Canvas::with_stride(Vector2I::new(3, 1), 4, Format::A8);
+    canvas.pixels = vec![0, 128, 255, 128];
+    canvas.apply_gamma_correction(&gamma_lut, [0, 0, 0]);
+    assert_eq!(canvas.pixels, [0, gamma_lut.correct(128, 0), 255, 128]);
+
+    let mut canvas = Canvas::new(Vector2I::new(1, 1), Format::Rgba32);
+    canvas.pixels = vec![128, 128, 128, 128];
+    canvas.apply_gamma_correction(&gamma_lut, [255, 0, 0]);
+    assert_eq!(
+        canvas.pixels,
+        [
+            gamma_lut.correct(128, 255),
+            gamma_lut.correct(128, 0),
+            gamma_lut.correct(128, 0),
+            gamma_lut.correct(128, 54),
+        ]
+    );
+}
+
+#[test]
+pub fn rasterize_glyph_with_exhausted_budget() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    let cancellation_token = CancellationToken::new();
+    cancellation_token.cancel();
+    let cancelled_budget = RasterizationBudget::new().with_cancellation_token(cancellation_token);
+    let expired_budget = RasterizationBudget::new().with_deadline(Instant::now());
+
+    for &rasterization_options in &[
+        RasterizationOptions::GrayscaleAa,
+        RasterizationOptions::Deterministic,
+    ] {
+        for &(budget, error) in &[
+            (&cancelled_budget, GlyphLoadingError::Cancelled),
+            (&expired_budget, GlyphLoadingError::BudgetExceeded),
+        ] {
+            let mut canvas = Canvas::new(Vector2I::splat(16), Format::A8);
+            let result = font.rasterize_glyph_with_budget(
+                &mut canvas,
+                1,
+                16.0,
+                Transform2F::from_translation(Vector2F::new(0.0, 16.0)),
+                HintingOptions::None,
+                rasterization_options,
+                budget,
+            );
+            assert_eq!(result, Err(error));
+            assert!(canvas.pixels.iter().all(|&value| value == 0));
+        }
+    }
+}
+
+#[test]
+pub fn get_glyph_layout_properties() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    assert_eq!(font.glyph_class(1), Some(GlyphClass::Base));
+    assert_eq!(font.glyph_class(166), Some(GlyphClass::Ligature));
+    assert_eq!(font.glyph_class(665), Some(GlyphClass::Mark));
+    assert_eq!(font.glyph_class(0x10000), None);
+    assert_eq!(font.mark_attachment_class(665), None);
+    assert!(font.attachment_points(1).is_empty());
+    // 'a' and U+0301 COMBINING ACUTE ACCENT.
+    assert_eq!(
+        font.mark_attachment_offset(66, 666),
+        Some(Vector2F::new(145.0, 0.0))
+    );
+    assert_eq!(font.mark_attachment_offset(66, 67), None);
+    assert_eq!(
+        font.ligature_carets(1923),
+        vec![LigatureCaret::Coordinate(274.0)]
+    );
+    assert!(font.ligature_carets(1).is_empty());
+}
+
+#[test]
+pub fn get_italic_angle_and_slant_translation() {
+    let regular = Font::from_path(TEST_FONT_COLLECTION_FILE_PATH, 0).unwrap();
+    assert_eq!(regular.italic_angle(), 0.0);
+    assert_eq!(regular.slant_translation(500.0), 0.0);
+    assert_eq!(regular.italic_correction(1), None);
+
+    let italic = Font::from_path(TEST_FONT_COLLECTION_FILE_PATH, 1).unwrap();
+    assert_eq!(italic.italic_angle(), -17.0);
+    assert!((italic.slant_translation(500.0) - 152.8653).abs() < 0.001);
+    assert_eq!(italic.italic_correction(1), None);
+}
+
+#[test]
+pub fn verify_checksums() {
+    for &path in &[TEST_FONT_FILE_PATH, FILE_PATH_EB_GARAMOND_TTF] {
+        let font = Font::from_path(path, 0).unwrap();
+        let report = font.verify_checksums().unwrap();
+        assert!(!report.tables.is_empty());
+        assert!(report.checksum_adjustment.is_some());
+        assert!(report.is_valid());
+    }
+
+    for font_index in 0..2 {
+        let font = Font::from_path(TEST_FONT_COLLECTION_FILE_PATH, font_index).unwrap();
+        let report = font.verify_checksums().unwrap();
+        assert!(report.checksum_adjustment.is_none());
+        assert!(report.is_valid());
+    }
+}
+
+#[test]
+pub fn verify_checksums_of_corrupted_font() {
+    let mut font_data = fs::read(TEST_FONT_FILE_PATH).unwrap();
+    let middle = font_data.len() / 2;
+    font_data[middle] ^= 0xff;
+
+    let font = Font::from_bytes(Arc::new(font_data), 0).unwrap();
+    let report = font.verify_checksums().unwrap();
+    assert!(!report.is_valid());
+    assert_eq!(
+        report
+            .tables
+            .iter()
+            .filter(|table_checksum| !table_checksum.is_valid())
+            .count(),
+        1
+    );
+    assert!(!report.checksum_adjustment.unwrap().is_valid());
+}
+
+#[test]
+pub fn get_font_classification() {
+    let font = Font::from_path(TEST_FONT_FILE_PATH, 0).unwrap();
+    let classification = font.classification().unwrap();
+    assert_eq!(classification.family_class, FamilyClass::OldstyleSerifs);
+    assert_eq!(classification.family_subclass, 2);
+    assert_eq!(classification.panose.serif_style(), Some(SerifStyle::Cove));
+    assert_eq!(classification.is_serif(), Some(true));
+
+    let font = Font::from_path(FILE_PATH_INCONSOLATA_TTF, 0).unwrap();
+    let classification = font.classification().unwrap();
+    assert_eq!(classification.family_class, FamilyClass::NoClassification);
+    assert_eq!(classification.is_serif(), None);
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn select_by_classification() {
+    let source = MemSource::from_fonts(
+        vec![
+            Handle::from_path(FILE_PATH_INCONSOLATA_TTF.into(), 0),
+            Handle::from_path(TEST_FONT_COLLECTION_FILE_PATH.into(), 0),
+            Handle::from_path(TEST_FONT_COLLECTION_FILE_PATH.into(), 1),
+        ]
+        .into_iter(),
+    )
+    .unwrap();
+    let italic = Font::from_path(TEST_FONT_COLLECTION_FILE_PATH, 1).unwrap();
+    let italic_panose = italic.classification().unwrap().panose;
+
+    let postscript_names = |query: &ClassificationQuery| -> Vec<String> {
+        source
+            .select_by_classification(query)
+            .unwrap()
+            .iter()
+            .map(|handle| handle.load().unwrap().postscript_name().unwrap())
+            .collect()
+    };
+    assert_eq!(
+        postscript_names(
+            ClassificationQuery::new()
+                .serif(true)
+                .family_class(FamilyClass::OldstyleSerifs)
+                .panose(italic_panose)
+        ),
+        vec!["EBGaramond12-Italic", "EBGaramond12-Regular"]
+    );
+    assert_eq!(postscript_names(&ClassificationQuery::new()).len(), 3);
+    assert!(source
+        .select_by_classification(ClassificationQuery::new().serif(false))
+        .is_err());
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn substitute_missing_font() {
+    let source = MemSource::from_fonts(
+        vec![
+            Handle::from_path(FILE_PATH_INCONSOLATA_TTF.into(), 0),
+            Handle::from_path(TEST_FONT_COLLECTION_FILE_PATH.into(), 0),
+            Handle::from_path(TEST_FONT_COLLECTION_FILE_PATH.into(), 1),
+        ]
+        .into_iter(),
+    )
+    .unwrap();
+
+    let substitution = source
+        .substitute("EB Garamond 12", &Properties::new())
+        .unwrap();
+    assert_eq!(substitution.reason, SubstitutionReason::Exact);
+
+    let italic = Font::from_path(TEST_FONT_COLLECTION_FILE_PATH, 1).unwrap();
+    let italic_panose = italic.classification().unwrap().panose;
+    let substitution = source
+        .substitute_with_panose(
+            "Minion Pro",
+            Properties::new().style(Style::Italic),
+            Some(italic_panose),
+        )
+        .unwrap();
+    assert_eq!(
+        substitution.reason,
+        SubstitutionReason::Similar {
+            family_name: "EB Garamond 12".to_owned(),
+            distance: 0,
+        }
+    );
+    assert_eq!(
+        substitution
+            .handle
+            .load()
+            .unwrap()
+            .postscript_name()
+            .unwrap(),
+        TEST_FONT_COLLECTION_POSTSCRIPT_NAME[1]
+    );
+
+    // There is no generic serif family to fall back to.
+    assert!(source.substitute("Minion Pro", &Properties::new()).is_err());
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn substitute_platform_substitutes_before_similar_fonts() {
+    // A source whose platform configuration substitutes Inconsolata for Consolas only.
+    struct ConfiguredSource(MemSource);
+
+    impl Source for ConfiguredSource {
+        fn all_fonts(&self) -> Result<Vec<Handle>, SelectionError> {
+            self.0.all_fonts()
+        }
+
+        fn all_families(&self) -> Result<Vec<String>, SelectionError> {
+            self.0.all_families()
+        }
+
+        fn list_fonts(&self) -> Result<Vec<Handle>, SelectionError> {
+            self.0.list_fonts()
+        }
+
+        fn select_family_by_name(&self, family_name: &str) -> Result<FamilyHandle, SelectionError> {
+            self.0.select_family_by_name(family_name)
+        }
+
+        fn select_platform_substitutes(&self, family_name: &str) -> Vec<String> {
+            match family_name {
+                "Consolas" => vec!["Inconsolata".to_owned()],
+                _ => vec![],
+            }
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_mut_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    let source = ConfiguredSource(
+        MemSource::from_fonts(
+            vec![
+                Handle::from_path(FILE_PATH_INCONSOLATA_TTF.into(), 0),
+                Handle::from_path(TEST_FONT_COLLECTION_FILE_PATH.into(), 1),
+            ]
+            .into_iter(),
+        )
+        .unwrap(),
+    );
+    let italic_panose = Font::from_path(TEST_FONT_COLLECTION_FILE_PATH, 1)
+        .unwrap()
+        .classification()
+        .unwrap()
+        .panose;
+    let substitution = source
+        .substitute_with_panose("Consolas", &Properties::new(), Some(italic_panose))
+        .unwrap();
+    assert_eq!(
+        substitution.reason,
+        SubstitutionReason::Platform("Inconsolata".to_owned())
+    );
+
+    // Families without a configured substitute go on to the PANOSE search.
+    let substitution = source
+        .substitute_with_panose("Minion Pro", &Properties::new(), Some(italic_panose))
+        .unwrap();
+    assert_eq!(
+        substitution.reason,
+        SubstitutionReason::Similar {
+            family_name: "EB Garamond 12".to_owned(),
+            distance: 0,
+        }
+    );
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn substitute_missing_serif_font_from_system() {
+    // The platform's generic fallback fonts aren't substitutes for this family in particular, so
+    // the family's PANOSE numbers or name decide the replacement.
+    let source = SystemSource::new();
+    let serif_panose = Font::from_path(TEST_FONT_FILE_PATH, 0)
+        .unwrap()
+        .classification()
+        .unwrap()
+        .panose;
+    let substitution = source
+        .substitute_with_panose(
+            "Font Kit Missing Serif",
+            &Properties::new(),
+            Some(serif_panose),
+        )
+        .unwrap();
+    assert!(matches!(
+        substitution.reason,
+        SubstitutionReason::Similar { .. }
+    ));
+
+    let substitution = source
+        .substitute("Font Kit Missing Serif", &Properties::new())
+        .unwrap();
+    assert_eq!(
+        substitution.reason,
+        SubstitutionReason::Generic(FamilyName::Serif)
+    );
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn select_aliased_family() {
+    let source =
+        MemSource::from_fonts(vec![Handle::from_path(TEST_FONT_FILE_PATH.into(), 0)].into_iter())
+            .unwrap();
+    let mut source = AliasSource::with_metric_compatible_aliases(Box::new(source));
+    source.add_alias("Garamond", &["Adobe Garamond", "EB Garamond 12"]);
+    source.add_alias("Minion Pro", &["Adobe Garamond"]);
+
+    let alias = source.alias("arial").unwrap();
+    assert_eq!(alias.target_family_names[0], "Liberation Sans");
+    assert!(alias.metric_compatible);
+    assert!(!source.alias("Garamond").unwrap().metric_compatible);
+
+    let families = source.all_families().unwrap();
+    assert!(families.iter().any(|family| family == "Garamond"));
+    assert!(!families.iter().any(|family| family == "Minion Pro"));
+
+    let family = source.select_family_by_name("GARAMOND").unwrap();
+    assert_eq!(family.fonts().len(), 1);
+    assert_eq!(
+        source.select_family_by_name("Minion Pro").unwrap_err(),
+        SelectionError::NotFound
+    );
+
+    let handle = source
+        .select_best_match(
+            &[FamilyName::Title("Garamond".to_owned())],
+            &Properties::new(),
+        )
+        .unwrap();
+    assert_eq!(
+        handle.load().unwrap().postscript_name().unwrap(),
+        TEST_FONT_POSTSCRIPT_NAME
+    );
+
+    let substitution = source.substitute("Garamond", &Properties::new()).unwrap();
+    assert_eq!(
+        substitution.reason,
+        SubstitutionReason::Alias("EB Garamond 12".to_owned())
+    );
+
+    assert!(source.remove_alias("garamond").is_some());
+    assert!(source.select_family_by_name("Garamond").is_err());
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn select_best_match_with_required_features() {
+    let source = MemSource::from_fonts(
+        vec![
+            Handle::from_path(FILE_PATH_INCONSOLATA_TTF.into(), 0),
+            Handle::from_path(TEST_FONT_FILE_PATH.into(), 0),
+        ]
+        .into_iter(),
+    )
+    .unwrap();
+    let family_names = [
+        FamilyName::Title("Inconsolata".to_owned()),
+        FamilyName::Title("EB Garamond 12".to_owned()),
+    ];
+
+    let font = source
+        .select_best_match(&family_names, &Properties::new())
+        .unwrap()
+        .load()
+        .unwrap();
+    assert_eq!(font.family_name(), "Inconsolata");
+
+    let font = source
+        .select_best_match_with_requirements(
+            &family_names,
+            &Properties::new(),
+            Requirements::new().features(Features::SMALL_CAPS),
+        )
+        .unwrap()
+        .load()
+        .unwrap();
+    assert_eq!(font.postscript_name().unwrap(), TEST_FONT_POSTSCRIPT_NAME);
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn select_best_match_with_visible_notdef() {
+    let family_names = [
+        FamilyName::Title("Inconsolata".to_owned()),
+        FamilyName::Title("EB Garamond 12".to_owned()),
+    ];
+    // EB Garamond with TrueType and with CFF outlines.
+    for &eb_garamond_path in &[FILE_PATH_EB_GARAMOND_TTF, TEST_FONT_FILE_PATH] {
+        let source = MemSource::from_fonts(
+            vec![
+                Handle::from_path(FILE_PATH_INCONSOLATA_TTF.into(), 0),
+                Handle::from_path(eb_garamond_path.into(), 0),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        let font = source
+            .select_best_match_with_requirements(
+                &family_names,
+                &Properties::new(),
+                Requirements::new().visible_notdef(true),
+            )
+            .unwrap()
+            .load()
+            .unwrap();
+        assert_eq!(font.postscript_name().unwrap(), TEST_FONT_POSTSCRIPT_NAME);
+    }
+}
+
+#[cfg(feature = "source")]
+#[test]
+pub fn select_best_match_with_requirements_skips_broken_fonts() {
+    // A source with a single family whose first font can't be loaded.
+    struct BrokenFamilySource;
+
+    impl Source for BrokenFamilySource {
+        fn all_fonts(&self) -> Result<Vec<Handle>, SelectionError> {
+            Ok(self.select_family_by_name("")?.fonts().to_vec())
+        }
+
+        fn all_families(&self) -> Result<Vec<String>, SelectionError> {
+            Ok(vec!["Broken".to_owned()])
+        }
+
+        fn list_fonts(&self) -> Result<Vec<Handle>, SelectionError> {
+            self.all_fonts()
+        }
+
+        fn select_family_by_name(&self, _: &str) -> Result<FamilyHandle, SelectionError> {
+            Ok(FamilyHandle::from_font_handles(
+                vec![
+                    Handle::from_memory(Arc::new(vec![0; 4]), 0),
+                    Handle::from_path(FILE_PATH_INCONSOLATA_TTF.into(), 0),
+                    Handle::from_path(TEST_FONT_FILE_PATH.into(), 0),
+                ]
+                .into_iter(),
+            ))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_mut_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    let font = BrokenFamilySource
+        .select_best_match_with_requirements(
+            &[FamilyName::Title("Broken".to_owned())],
+            &Properties::new(),
+            Requirements::new().visible_notdef(true),
+        )
+        .unwrap()
+        .load()
+        .unwrap();
+    assert_eq!(font.postscript_name().unwrap(), TEST_FONT_POSTSCRIPT_NAME);
+}
+
 #[cfg(feature = "source")]
 #[test]
 pub fn rasterize_glyph_with_grayscale_aa() {
@@ -1270,3 +2060,109 @@ fn stripe_width(pixels: &[u8]) -> Option<u32> {
     assert_eq!(x, pixels.len());
     Some(stripe_width)
 }
+
+// The deterministic rasterizer only depends on the glyph outline, so every loader must produce
+// exactly this image. The glyph is made of straight lines only, so curve flattening doesn't come
+// into play either.
+#[test]
+pub fn rasterize_glyph_deterministic_golden() {
+    // 'E' in Inconsolata.
+    const GLYPH_ID: u32 = 45;
+    #[rustfmt::skip]
+    const GOLDEN: [u8; 80] = [
+        14, 251, 251, 251, 251, 251, 251, 14,
+        14, 255,   2,   2,   2,   2,   2,  0,
+        14, 255,   0,   0,   0,   0,   0,  0,
+        14, 255,   0,   0,   0,   0,   0,  0,
+        14, 255, 182, 182, 182, 182,  32,  0,
+        14, 255,  80,  80,  80,  80,  14,  0,
+        14, 255,   0,   0,   0,   0,   0,  0,
+        14, 255,   0,   0,   0,   0,   0,  0,
+        14, 255,   0,   0,   0,   0,   0,  0,
+        14, 255, 253, 253, 253, 253, 253,  2,
+    ];
+
+    let font = Font::from_path(FILE_PATH_INCONSOLATA_TTF, 0).unwrap();
+    let bounds = font
+        .raster_bounds(
+            GLYPH_ID,
+            16.0,
+            Transform2F::default(),
+            HintingOptions::None,
+            RasterizationOptions::Deterministic,
+        )
+        .unwrap();
+    assert_eq!(
+        bounds,
+        RectI::new(Vector2I::new(0, -10), Vector2I::new(8, 10))
+    );
+
+    // Previous contents of the canvas are overwritten.
+    let mut canvas = Canvas::new(bounds.size(), Format::A8);
+    canvas.pixels.iter_mut().for_each(|value| *value = 255);
+    font.rasterize_glyph(
+        &mut canvas,
+        GLYPH_ID,
+        16.0,
+        Transform2F::from_translation(-bounds.origin().to_f32()),
+        HintingOptions::None,
+        RasterizationOptions::Deterministic,
+    )
+    .unwrap();
+    // Allow for rounding differences in the outlines the platform APIs return.
+    for (&value, &expected) in canvas.pixels.iter().zip(GOLDEN.iter()) {
+        assert!((value as i32 - expected as i32).abs() <= 1);
+    }
+}
+
+// Returns a copy of the given font with the given tables added to it.
+fn add_font_tables(font_data: &[u8], mut new_tables: Vec<(u32, Vec<u8>)>) -> Vec<u8> {
+    let read_u32 = |offset: usize| {
+        u32::from_be_bytes([
+            font_data[offset],
+            font_data[offset + 1],
+            font_data[offset + 2],
+            font_data[offset + 3],
+        ])
+    };
+    let table_count = u16::from_be_bytes([font_data[4], font_data[5]]) as usize;
+    let mut tables: Vec<(u32, Vec<u8>)> = (0..table_count)
+        .map(|table_index| {
+            let record_offset = 12 + table_index * 16;
+            let offset = read_u32(record_offset + 8) as usize;
+            let length = read_u32(record_offset + 12) as usize;
+            (
+                read_u32(record_offset),
+                font_data[offset..(offset + length)].to_vec(),
+            )
+        })
+        .collect();
+    tables.append(&mut new_tables);
+    tables.sort_by_key(|&(tag, _)| tag);
+
+    let table_count = tables.len();
+    let entry_selector = (table_count as f32).log2().floor() as u16;
+    let search_range = 16 << entry_selector;
+    let mut output = font_data[0..4].to_vec();
+    for &value in &[
+        table_count as u16,
+        search_range,
+        entry_selector,
+        table_count as u16 * 16 - search_range,
+    ] {
+        output.extend_from_slice(&value.to_be_bytes());
+    }
+    let mut offset = 12 + table_count * 16;
+    for (tag, table) in &tables {
+        output.extend_from_slice(&tag.to_be_bytes());
+        output.extend_from_slice(&[0; 4]);
+        output.extend_from_slice(&(offset as u32).to_be_bytes());
+        output.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) & !3;
+    }
+    for (_, table) in &tables {
+        output.extend_from_slice(table);
+        output.resize((output.len() + 3) & !3, 0);
+    }
+    output
+}